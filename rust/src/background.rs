@@ -0,0 +1,133 @@
+//! Background mode, for library maintenance that should not get in the way of foreground use.
+//!
+//! In background mode the pipeline threads run at the lowest priority, only one file is fetched
+//! and written at a time, writes are paced to a fixed disk bandwidth, and work is deferred while
+//! the machine runs on battery or is on a metered connection.
+
+use std::sync::atomic::Ordering;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use thread_priority::ThreadPriority;
+
+use crate::{RunContext, CANCEL_POLL_INTERVAL};
+
+/// Disk bandwidth that background writes are paced to, in bytes per second.
+const IO_RATE: u64 = 5_000_000;
+
+/// How long the power source and connection checks are trusted before they are repeated.
+const RECHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Runs the calling thread at the lowest priority the platform allows.
+pub fn lower_thread_priority() {
+    let _ = thread_priority::set_current_thread_priority(ThreadPriority::Min);
+}
+
+/// Sleeps after writing `bytes` in `elapsed`, so that writes average out at `IO_RATE`.
+pub fn pace_write(bytes: u64, elapsed: Duration, run: &RunContext) {
+    let target = Duration::from_secs_f64(bytes as f64 / IO_RATE as f64);
+    let deadline = Instant::now() + target.saturating_sub(elapsed);
+    while Instant::now() < deadline && !run.cancel.is_cancelled() {
+        thread::sleep(CANCEL_POLL_INTERVAL.min(deadline - Instant::now()));
+    }
+}
+
+/// Remembers why work is deferred, so the checks don't run for every file.
+#[derive(Default)]
+pub struct Conditions {
+    checked: Mutex<Option<(Instant, Option<&'static str>)>>,
+}
+
+impl Conditions {
+    /// Returns why work should be deferred right now, if it should.
+    fn defer_reason(&self) -> Option<&'static str> {
+        let mut checked = self.checked.lock().unwrap();
+        match *checked {
+            Some((at, reason)) if at.elapsed() < RECHECK_INTERVAL => reason,
+            _ => {
+                let reason = if on_battery() {
+                    Some("running on battery")
+                } else if on_metered_connection() {
+                    Some("on a metered connection")
+                } else {
+                    None
+                };
+                *checked = Some((Instant::now(), reason));
+                reason
+            }
+        }
+    }
+}
+
+/// Blocks while the machine is on battery or a metered connection.
+///
+/// The run's `deferred` flag is set meanwhile so the UI can show it, and the pause and resume are
+/// logged once. Returns false if the run was cancelled while waiting.
+pub fn wait_until_allowed(run: &RunContext) -> bool {
+    while let Some(reason) = run.conditions.defer_reason() {
+        if !run.deferred.swap(true, Ordering::SeqCst) {
+            run.logs.lock().unwrap().push(format!("⏸ Background mode: pausing while {}", reason));
+        }
+        if run.cancel.is_cancelled() {
+            return false;
+        }
+        thread::sleep(CANCEL_POLL_INTERVAL);
+    }
+    if run.deferred.swap(false, Ordering::SeqCst) {
+        run.logs.lock().unwrap().push("▶ Background mode: resuming".to_string());
+    }
+    !run.cancel.is_cancelled()
+}
+
+/// Returns true if the machine has a mains power supply and it is unplugged.
+#[cfg(target_os = "linux")]
+fn on_battery() -> bool {
+    let Ok(supplies) = std::fs::read_dir("/sys/class/power_supply") else {
+        return false;
+    };
+    let read = |path: std::path::PathBuf| std::fs::read_to_string(path).unwrap_or_default().trim().to_string();
+    let mains: Vec<_> = supplies
+        .filter_map(Result::ok)
+        .map(|supply| supply.path())
+        .filter(|supply| read(supply.join("type")) == "Mains")
+        .collect();
+    !mains.is_empty() && mains.iter().all(|supply| read(supply.join("online")) == "0")
+}
+
+#[cfg(windows)]
+fn on_battery() -> bool {
+    use winapi::um::winbase::{GetSystemPowerStatus, SYSTEM_POWER_STATUS};
+    // SAFETY: SYSTEM_POWER_STATUS is plain data, and GetSystemPowerStatus only writes to it.
+    unsafe {
+        let mut status: SYSTEM_POWER_STATUS = std::mem::zeroed();
+        GetSystemPowerStatus(&mut status) != 0 && status.ACLineStatus == 0
+    }
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+fn on_battery() -> bool {
+    false
+}
+
+/// Asks NetworkManager whether the primary connection is metered. Systems without it count as
+/// unmetered.
+#[cfg(target_os = "linux")]
+fn on_metered_connection() -> bool {
+    let output = std::process::Command::new("busctl")
+        .args([
+            "get-property",
+            "org.freedesktop.NetworkManager",
+            "/org/freedesktop/NetworkManager",
+            "org.freedesktop.NetworkManager",
+            "Metered",
+        ])
+        .output();
+    // NMMetered: 1 is "yes" and 3 is "guessed yes"
+    output.is_ok_and(|output| matches!(String::from_utf8_lossy(&output.stdout).trim(), "u 1" | "u 3"))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn on_metered_connection() -> bool {
+    false
+}
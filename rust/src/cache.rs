@@ -0,0 +1,121 @@
+//! Persistent cache of provider lookups, so runs over the same library don't ask again.
+//!
+//! Lookups are keyed by provider, artist, title, album, duration and the minimum match score, and
+//! stored in a JSON file in the cache directory, which is written at the end of every run. Found
+//! lyrics are kept until the cache is cleared, while "not found" answers expire after
+//! `NOT_FOUND_TTL`, as the providers keep growing.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::providers::Query;
+use crate::LyricsResult;
+
+/// How long a provider not having lyrics for a track is remembered.
+pub const NOT_FOUND_TTL: Duration = Duration::from_secs(7 * 24 * 3600);
+
+#[derive(Clone, Serialize, Deserialize)]
+struct CachedLookup {
+    /// The record the provider matched, or None if it had none.
+    result: Option<LyricsResult>,
+    /// Unix time of the lookup.
+    cached_at: i64,
+}
+
+pub struct LookupCache {
+    path: PathBuf,
+    entries: Mutex<HashMap<String, CachedLookup>>,
+    /// Set when entries were added or removed since the cache was loaded or saved.
+    changed: AtomicBool,
+    /// Lookups answered from the cache and not, since it was loaded.
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+}
+
+impl LookupCache {
+    /// Loads the cache stored at `path`, or starts an empty one if there is none or it can't
+    /// be read.
+    pub fn load(path: &Path) -> Self {
+        let entries = fs::read_to_string(path).ok().and_then(|json| serde_json::from_str(&json).ok()).unwrap_or_default();
+        Self {
+            path: path.to_path_buf(),
+            entries: Mutex::new(entries),
+            changed: AtomicBool::new(false),
+            hits: AtomicUsize::new(0),
+            misses: AtomicUsize::new(0),
+        }
+    }
+
+    /// The remembered lookup of a track: Some(None) if `provider` had no match for it, None if
+    /// it wasn't looked up yet or its "not found" answer has expired.
+    pub fn get(&self, provider: &str, query: &Query, min_score: u8) -> Option<Option<LyricsResult>> {
+        let entries = self.entries.lock().unwrap();
+        let now = Utc::now().timestamp();
+        let result = entries
+            .get(&key(provider, query, min_score))
+            .filter(|lookup| lookup.result.is_some() || now.saturating_sub(lookup.cached_at) <= NOT_FOUND_TTL.as_secs() as i64)
+            .map(|lookup| lookup.result.clone());
+        let counter = if result.is_some() { &self.hits } else { &self.misses };
+        counter.fetch_add(1, Ordering::SeqCst);
+        result
+    }
+
+    /// Number of calls to `get` that were answered from the cache and that weren't.
+    pub fn hit_counts(&self) -> (usize, usize) {
+        (self.hits.load(Ordering::SeqCst), self.misses.load(Ordering::SeqCst))
+    }
+
+    pub fn insert(&self, provider: &str, query: &Query, min_score: u8, result: Option<LyricsResult>) {
+        let lookup = CachedLookup { result, cached_at: Utc::now().timestamp() };
+        self.entries.lock().unwrap().insert(key(provider, query, min_score), lookup);
+        self.changed.store(true, Ordering::SeqCst);
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    pub fn clear(&self) -> io::Result<()> {
+        self.entries.lock().unwrap().clear();
+        self.changed.store(false, Ordering::SeqCst);
+        match fs::remove_file(&self.path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    /// Writes the cache to disk if it changed.
+    pub fn save(&self) -> io::Result<()> {
+        if !self.changed.swap(false, Ordering::SeqCst) {
+            return Ok(());
+        }
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let json = serde_json::to_string(&*self.entries.lock().unwrap())?;
+        // Written next to the cache and renamed over it, so a crash can't leave half a file
+        let temp = self.path.with_extension("json.tmp");
+        fs::write(&temp, json)?;
+        fs::rename(&temp, &self.path)
+    }
+}
+
+/// Key of a provider's lookup of a track, ignoring case and surrounding whitespace. Durations are
+/// rounded to the second, as lrclib matches them. The album is part of it, as lrclib's get
+/// endpoint is asked for the recording on that album. So is `min_score`, the
+/// `MatchRules::min_score` search results were accepted with, so changing it takes effect for
+/// tracks looked up before.
+fn key(provider: &str, query: &Query, min_score: u8) -> String {
+    let duration = query.duration.map_or(String::new(), |duration| duration.as_secs_f64().round().to_string());
+    let (artist, title) = (query.artist.trim().to_lowercase(), query.title.trim().to_lowercase());
+    let album = query.album.map_or(String::new(), |album| album.trim().to_lowercase());
+    format!("{}\u{1f}{}\u{1f}{}\u{1f}{}\u{1f}{}\u{1f}{}", provider, artist, title, album, duration, min_score)
+}
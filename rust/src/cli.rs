@@ -0,0 +1,205 @@
+//! Headless mode for libraries on servers and NAS boxes without a display.
+//!
+//! `LyricsDownloader --cli <folder> [--lrc] [--embed] [--dry-run]` runs the same pipeline as the
+//! GUI with the active profile's settings, printing the log to stdout as it goes. An M3U or M3U8
+//! playlist can be given instead of the folder. `.lrc` files are written unless only `--embed` is
+//! given; with both flags every file gets both from a single lookup.
+//! The exit code is 0 if lyrics were (or, with `--dry-run`, would be) written for at least one
+//! file, 1 if none were, and 2 for invalid arguments.
+//!
+//! `--watch` keeps running after the folder is done, as a daemon, and fetches the lyrics of new
+//! files as they arrive, until it gets Ctrl+C or SIGTERM. These stop any run after the file being
+//! written, so no tag is left half written. `--metrics <address>` serves the run's
+//! counters for Prometheus at that address, see `metrics`.
+
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+use crate::cache::LookupCache;
+use crate::config::AppConfig;
+use crate::failure::FailureCounts;
+use crate::manifest::ManifestRecorder;
+use crate::metrics;
+use crate::network::{self, AdaptiveConcurrency, NetworkStats};
+use crate::paths::app_paths;
+use crate::playlist;
+use crate::progress::ProgressState;
+use crate::rate_limit::RateLimiters;
+use crate::search::LyricsIndex;
+use crate::watch::{self, FolderWatcher};
+use crate::{
+    background, new_audio_files, process_folder, process_new_files, CancellationToken, OutputMode, PipelineConfig, RunContext,
+    CANCEL_POLL_INTERVAL, LRCLIB,
+};
+
+const USAGE: &str =
+    "Usage: LyricsDownloader --cli <folder or playlist> [--lrc] [--embed] [--dry-run] [--watch] [--metrics <address:port>]";
+
+/// Runs the pipeline on the folder named in `args` (the arguments after `--cli`) and returns the
+/// process exit code.
+pub fn run(args: &[String]) -> i32 {
+    attach_console();
+    let mut folder = None;
+    let (mut lrc, mut embed) = (false, false);
+    let (mut dry_run, mut watching) = (false, false);
+    let mut metrics_address = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--lrc" => lrc = true,
+            "--embed" => embed = true,
+            "--dry-run" => dry_run = true,
+            "--watch" => watching = true,
+            "--metrics" => match args.next() {
+                Some(address) => metrics_address = Some(address),
+                None => {
+                    eprintln!("{}", USAGE);
+                    return 2;
+                }
+            },
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return 0;
+            }
+            _ if arg.starts_with('-') || folder.is_some() => {
+                eprintln!("{}", USAGE);
+                return 2;
+            }
+            _ => folder = Some(PathBuf::from(arg)),
+        }
+    }
+    let mode = match (lrc, embed) {
+        (true, true) => OutputMode::Both,
+        (false, true) => OutputMode::Embed,
+        (_, false) => OutputMode::Lrc,
+    };
+    let Some(folder) = folder.filter(|folder| folder.is_dir() || (playlist::is_playlist(folder) && folder.is_file() && !watching)) else {
+        eprintln!("{}", USAGE);
+        return 2;
+    };
+    let metrics_listener = match metrics_address.map(|address| (address, TcpListener::bind(address))) {
+        None => None,
+        Some((_, Ok(listener))) => Some(listener),
+        Some((address, Err(e))) => {
+            eprintln!("❌ Could not serve metrics on {}: {}", address, e);
+            return 2;
+        }
+    };
+
+    let paths = app_paths();
+    let app_config = paths.as_ref().map_or_else(AppConfig::default, |paths| AppConfig::load(&paths.config()));
+    if let Err(e) = network::configure(&app_config.http) {
+        eprintln!("⚠ Ignoring the proxy and certificate settings: {}", e);
+    }
+    // Nobody is there to review the lyrics
+    let config = PipelineConfig { review: false, ..app_config.pipeline };
+    let rate_limiters = Arc::new(RateLimiters::default());
+    rate_limiters.set_rate(LRCLIB, config.lrclib_rate);
+    rate_limiters.set_total_rate(config.total_rate);
+    let run = RunContext {
+        logs: Default::default(),
+        cancel: CancellationToken::default(),
+        pause: Default::default(),
+        rate_limiters,
+        network: Arc::new(NetworkStats::default()),
+        concurrency: Arc::new(AdaptiveConcurrency::new(config.fetch_threads)),
+        low_space: Default::default(),
+        deferred: Default::default(),
+        conditions: background::Conditions::default(),
+        scanned: Default::default(),
+        written: Default::default(),
+        kept: AtomicUsize::new(0),
+        failures: FailureCounts::default(),
+        manifest: ManifestRecorder::new(Default::default()),
+        journal: None,
+        undo: None,
+        app_config,
+        search_index: paths.as_ref().and_then(|paths| LyricsIndex::open(&paths.search_index()).ok()).map(Arc::new),
+        lookup_cache: paths.map(|paths| Arc::new(LookupCache::load(&paths.lookup_cache()))),
+        review_decisions: None,
+        lookups: Default::default(),
+        prompts: Default::default(),
+        reviews: Default::default(),
+        latest_lyrics: Default::default(),
+        redactions: Default::default(),
+        metadata_overrides: Default::default(),
+        progress: Arc::new(ProgressState::default()),
+        dry_run,
+        refresh_lookups: false,
+    };
+
+    run.progress.reset();
+    let cancel = run.cancel.clone();
+    if let Err(e) = ctrlc::set_handler(move || cancel.cancel()) {
+        eprintln!("⚠ Stopping won't wait for the file being written: {}", e);
+    }
+    let done = AtomicBool::new(false);
+    thread::scope(|scope| {
+        if let Some(listener) = &metrics_listener {
+            let address = listener.local_addr().map_or_else(|_| String::new(), |address| address.to_string());
+            run.logs.lock().unwrap().push(format!("[INFO] Serving metrics on http://{}/metrics", address));
+            scope.spawn(|| metrics::serve(listener, &run, &done));
+        }
+        let worker = scope.spawn(|| {
+            process_folder(&folder, mode, config, &run);
+            if watching && !run.cancel.is_cancelled() {
+                watch_folder(&folder, mode, config, &run);
+            }
+        });
+        loop {
+            // Checked before printing, so the lines logged last aren't missed
+            let finished = worker.is_finished();
+            // Taken out of the log, so it doesn't grow for as long as a watch runs
+            let lines: Vec<String> = run.logs.lock().unwrap().drain(..).collect();
+            for line in lines {
+                println!("{}", line);
+            }
+            if finished {
+                break;
+            }
+            thread::sleep(CANCEL_POLL_INTERVAL);
+        }
+        done.store(true, Ordering::SeqCst);
+    });
+    if run.written.load(Ordering::SeqCst) == 0 {
+        1
+    } else {
+        0
+    }
+}
+
+/// Fetches the lyrics of new files in `folder` as they settle, until the run is cancelled or
+/// the folder can't be watched.
+fn watch_folder(folder: &Path, mode: OutputMode, config: PipelineConfig, run: &RunContext) {
+    let watcher = match FolderWatcher::start(folder, || {}) {
+        Ok(watcher) => watcher,
+        Err(e) => {
+            run.logs.lock().unwrap().push(format!("❌ Could not watch {}: {}", folder.display(), e));
+            return;
+        }
+    };
+    run.logs.lock().unwrap().push(format!("[INFO] Watching {} for new audio files", folder.display()));
+    while run.cancel.sleep(watch::SETTLE_TIME) {
+        let files = new_audio_files(watcher.take_settled(), folder, &run.app_config.scan_filter);
+        if !files.is_empty() {
+            process_new_files(files, mode, config, run);
+        }
+    }
+    run.logs.lock().unwrap().push(format!("[INFO] Stopped watching {}", folder.display()));
+}
+
+/// Connects stdout to the console the app was started from. The app is built for the Windows
+/// GUI subsystem, which doesn't get a console of its own.
+#[cfg(windows)]
+fn attach_console() {
+    use winapi::um::wincon::{AttachConsole, ATTACH_PARENT_PROCESS};
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+fn attach_console() {}
@@ -0,0 +1,156 @@
+//! Settings that persist between sessions, stored as `config.json` in the config directory.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::filter::ScanFilter;
+use crate::matching::MatchRules;
+use crate::mqtt::MqttConfig;
+use crate::network::HttpConfig;
+use crate::normalize::QueryRules;
+use crate::notification::NotificationConfig;
+use crate::providers::{ProviderKind, ProviderSetting};
+use crate::theme::Theme;
+use crate::translate::TranslationConfig;
+use crate::PipelineConfig;
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct AppConfig {
+    /// Library folder last selected in this profile.
+    #[serde(default)]
+    pub folder: Option<PathBuf>,
+    /// Further folders processed in the same run as `folder`, after it or alongside it.
+    #[serde(default)]
+    pub queue: Vec<PathBuf>,
+    /// Outputs checked next to "Fetch lyrics" when the app was last used.
+    #[serde(default)]
+    pub outputs: Outputs,
+    /// Size and position of the window when the app was last closed.
+    #[serde(default)]
+    pub window: Option<WindowGeometry>,
+    /// Process the queued folders at the same time instead of one after another.
+    #[serde(default)]
+    pub queue_parallel: bool,
+    /// Artists whose tracks are never looked up, such as instrumental or ambient artists.
+    #[serde(default)]
+    pub never_fetch: Vec<String>,
+    /// Files left out of scans, such as audiobooks, podcasts and sound effects.
+    #[serde(default)]
+    pub scan_filter: ScanFilter,
+    /// Proxy, certificates and timeout of requests to the lyrics providers.
+    #[serde(default)]
+    pub http: HttpConfig,
+    /// Broker that run events are published to.
+    #[serde(default)]
+    pub mqtt: MqttConfig,
+    /// Desktop notifications shown when a run ends.
+    #[serde(default)]
+    pub notifications: NotificationConfig,
+    #[serde(default)]
+    pub theme: Theme,
+    /// USLT frames lyrics are embedded in, in ID3v2 tags. Empty for a single frame without
+    /// language or description.
+    #[serde(default)]
+    pub lyrics_frames: Vec<LyricsFrame>,
+    /// Order lyrics providers are asked in, see `providers()`.
+    #[serde(default)]
+    pub providers: Vec<ProviderSetting>,
+    /// Ask the providers in the configured order for every track, instead of asking those covering
+    /// the script of a track's title first, such as NetEase for Chinese, Japanese and Korean ones.
+    #[serde(default)]
+    pub keep_provider_order: bool,
+    /// API token of the Genius provider, from https://genius.com/api-clients.
+    #[serde(default)]
+    pub genius_token: String,
+    /// How titles and artists are cleaned up for a second lookup when the first finds nothing.
+    #[serde(default)]
+    pub query_rules: QueryRules,
+    /// Translations added beneath the lyrics found.
+    #[serde(default)]
+    pub translation: TranslationConfig,
+    /// How closely a search result has to match a track to be used.
+    #[serde(default)]
+    pub match_rules: MatchRules,
+    /// Settings of the next run, as last set under Settings.
+    #[serde(default)]
+    pub pipeline: PipelineConfig,
+}
+
+/// Where lookup runs started with "Fetch lyrics" put the lyrics.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct Outputs {
+    pub lrc: bool,
+    pub embed: bool,
+}
+
+impl Default for Outputs {
+    fn default() -> Self {
+        Self { lrc: true, embed: false }
+    }
+}
+
+/// Outer position and inner size of the window, in points.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WindowGeometry {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    pub maximized: bool,
+}
+
+/// A USLT frame that embedded lyrics are written to.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct LyricsFrame {
+    /// ISO 639-2 language code, such as `eng`. Some players only show lyrics in their language.
+    pub language: String,
+    /// Content descriptor. Frames are told apart by it, so every frame needs its own.
+    pub description: String,
+}
+
+impl LyricsFrame {
+    /// The language as stored in the frame, or `XXX` (unknown) if it isn't a three-letter code.
+    pub fn language_code(&self) -> [u8; 3] {
+        match *self.language.trim().as_bytes() {
+            [a, b, c] if [a, b, c].iter().all(u8::is_ascii_alphabetic) => [a, b, c],
+            _ => *b"XXX",
+        }
+    }
+}
+
+impl AppConfig {
+    /// Reads the config file at `path`. A missing or unreadable file gives the default config.
+    pub fn load(path: &Path) -> Self {
+        fs::read_to_string(path).ok().and_then(|text| serde_json::from_str(&text).ok()).unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)
+    }
+
+    /// Lyrics providers in the order they are asked. Providers missing from the config, such as
+    /// ones added after it was saved, follow the configured ones, see
+    /// `ProviderKind::enabled_by_default`.
+    pub fn providers(&self) -> Vec<ProviderSetting> {
+        let mut providers = self.providers.clone();
+        for kind in ProviderKind::ALL {
+            if !providers.iter().any(|setting| setting.kind == kind) {
+                providers.push(ProviderSetting { kind, enabled: kind.enabled_by_default() });
+            }
+        }
+        providers
+    }
+
+    /// Returns true if `artist` is marked as never fetch. Names are compared ignoring case and
+    /// surrounding whitespace.
+    pub fn is_never_fetch(&self, artist: &str) -> bool {
+        let artist = artist.trim().to_lowercase();
+        self.never_fetch.iter().any(|name| name.trim().to_lowercase() == artist)
+    }
+}
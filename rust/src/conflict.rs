@@ -0,0 +1,167 @@
+//! Handling of files that already have lyrics.
+//!
+//! Before a write replaces an existing `.lrc` file or embedded lyrics, the old and new lyrics are
+//! compared line by line. Depending on the run's `OverwritePolicy` the new lyrics are written and
+//! the changes logged (optionally after backing up the old ones), the existing lyrics are kept,
+//! the better of the two is picked, or the user is asked to decide in the GUI. The same policy
+//! applies to `.lrc` files and embedded lyrics.
+
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+use similar::{ChangeTag, TextDiff};
+
+use crate::{lrc, RunContext, CANCEL_POLL_INTERVAL};
+
+/// Most changed lines written to the log for a single file.
+const MAX_LOGGED_LINES: usize = 20;
+
+/// What to do when a file already has lyrics that differ from the ones found.
+#[derive(Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverwritePolicy {
+    /// Replace the existing lyrics and log what changed.
+    #[default]
+    Overwrite,
+    /// Like `Overwrite`, but first save the existing lyrics to a `.bak` file next to the audio
+    /// file, see `backup_path`.
+    BackupThenOverwrite,
+    /// Leave files that already have lyrics alone, without looking up new ones.
+    Keep,
+    /// Replace existing lyrics only if they are plain text without timestamps.
+    PreferSynced,
+    /// Replace existing lyrics only if the new ones have more text.
+    PreferLonger,
+    /// Show what changed and let the user decide for each file.
+    Ask,
+    /// Replace only `.lrc` files this app wrote with the LRC header option on, leaving
+    /// hand-made files and embedded lyrics alone.
+    OwnOnly,
+}
+
+impl OverwritePolicy {
+    pub const ALL: [OverwritePolicy; 7] = [
+        OverwritePolicy::Overwrite,
+        OverwritePolicy::BackupThenOverwrite,
+        OverwritePolicy::Keep,
+        OverwritePolicy::PreferSynced,
+        OverwritePolicy::PreferLonger,
+        OverwritePolicy::Ask,
+        OverwritePolicy::OwnOnly,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            OverwritePolicy::Overwrite => "Overwrite",
+            OverwritePolicy::BackupThenOverwrite => "Back up to .bak, then overwrite",
+            OverwritePolicy::Keep => "Skip files that have lyrics",
+            OverwritePolicy::PreferSynced => "Prefer synced",
+            OverwritePolicy::PreferLonger => "Prefer longer",
+            OverwritePolicy::Ask => "Ask for each file",
+            OverwritePolicy::OwnOnly => "Replace only this app's .lrc files",
+        }
+    }
+}
+
+/// A file waiting for the user to decide whether its lyrics are replaced.
+pub struct ConflictPrompt {
+    pub file: PathBuf,
+    /// The changed lines, as returned by `line_diff`.
+    pub diff: Vec<String>,
+    reply: mpsc::Sender<bool>,
+}
+
+impl ConflictPrompt {
+    /// Answers the prompt; with `replace` set the new lyrics are written.
+    pub fn answer(self, replace: bool) {
+        let _ = self.reply.send(replace);
+    }
+}
+
+/// Prompts waiting for an answer, oldest first, shared between a run and the UI.
+pub type PromptQueue = Arc<Mutex<VecDeque<ConflictPrompt>>>;
+
+/// Returns the lines that differ between `old` and `new`, prefixed with "-" or "+".
+///
+/// Line endings are normalized first, so lyrics that only differ in CRLF vs LF or a trailing
+/// newline are considered identical and yield an empty diff.
+pub fn line_diff(old: &str, new: &str) -> Vec<String> {
+    let normalize = |text: &str| text.lines().map(|line| format!("{}\n", line)).collect::<String>();
+    let (old, new) = (normalize(old), normalize(new));
+    TextDiff::from_lines(&old, &new)
+        .iter_all_changes()
+        .filter_map(|change| {
+            let sign = match change.tag() {
+                ChangeTag::Delete => '-',
+                ChangeTag::Insert => '+',
+                ChangeTag::Equal => return None,
+            };
+            Some(format!("{} {}", sign, change.value().trim_end_matches('\n')))
+        })
+        .collect()
+}
+
+/// Decides whether the existing lyrics `old` of `file` are replaced with `new`.
+///
+/// Identical lyrics are always written. Otherwise `policy` decides, and the changed lines are
+/// logged if the lyrics are replaced. With `OverwritePolicy::Ask` this blocks until the user
+/// answers in the GUI, except in dry runs, which count the file as replaced. Returns false if the existing lyrics are kept
+/// or the run was cancelled while waiting for an answer.
+pub fn should_replace(file: &Path, old: &str, new: &str, policy: OverwritePolicy, run: &RunContext) -> bool {
+    let diff = line_diff(old, new);
+    if diff.is_empty() {
+        return true;
+    }
+    let replace = match policy {
+        OverwritePolicy::Overwrite | OverwritePolicy::BackupThenOverwrite => true,
+        OverwritePolicy::Keep => false,
+        OverwritePolicy::PreferSynced => !lrc::is_synced(old) && lrc::is_synced(new),
+        OverwritePolicy::PreferLonger => lrc::text_len(new) > lrc::text_len(old),
+        // A dry run writes nothing, so there is nothing to ask about yet
+        OverwritePolicy::Ask if run.dry_run => true,
+        OverwritePolicy::Ask => ask(file, diff.clone(), run),
+        OverwritePolicy::OwnOnly => lrc::is_own_output(old),
+    };
+    if replace {
+        log_diff(file, &diff, run);
+    }
+    replace
+}
+
+/// Where `OverwritePolicy::BackupThenOverwrite` saves the existing lyrics of `file` before they
+/// are replaced: next to the `.lrc` or `.txt` file `output` with `.bak` appended, or for embedded
+/// lyrics (when `output` is the audio file itself) in a `.lrc.bak` file named after the audio file.
+pub fn backup_path(file: &Path, output: &Path) -> PathBuf {
+    if output == file {
+        return file.with_extension("lrc.bak");
+    }
+    let mut backup = output.as_os_str().to_owned();
+    backup.push(".bak");
+    PathBuf::from(backup)
+}
+
+/// Queues a prompt for the GUI and waits for the answer, or until the run is cancelled.
+fn ask(file: &Path, diff: Vec<String>, run: &RunContext) -> bool {
+    let (reply, answer) = mpsc::channel();
+    run.prompts.lock().unwrap().push_back(ConflictPrompt { file: file.to_path_buf(), diff, reply });
+    loop {
+        match answer.recv_timeout(CANCEL_POLL_INTERVAL) {
+            Ok(replace) => return replace,
+            Err(RecvTimeoutError::Timeout) if !run.cancel.is_cancelled() => {}
+            Err(_) => return false,
+        }
+    }
+}
+
+fn log_diff(file: &Path, diff: &[String], run: &RunContext) {
+    let mut logs = run.logs.lock().unwrap();
+    let verb = if run.dry_run { "Would replace" } else { "Replacing" };
+    logs.push(format!("✎ {} existing lyrics of {}:", verb, file.display()));
+    logs.extend(diff.iter().take(MAX_LOGGED_LINES).map(|line| format!("    {}", line)));
+    if diff.len() > MAX_LOGGED_LINES {
+        logs.push(format!("    … {} more changed lines", diff.len() - MAX_LOGGED_LINES));
+    }
+}
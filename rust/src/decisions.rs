@@ -0,0 +1,148 @@
+//! Accept and reject decisions made in review mode, remembered so the same lyrics aren't asked
+//! about again.
+//!
+//! Decisions are keyed by the track's artist, title and album and a hash of the lyrics, not by
+//! the file's path, so they can be exported from one machine and imported on another holding the
+//! same library in a different place. They are stored as JSON in the data directory, rewritten
+//! with every decision. An import keeps the newer of two decisions about the same lyrics.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Decision {
+    pub artist: String,
+    pub title: String,
+    pub album: Option<String>,
+    pub lyrics_sha256: String,
+    pub accept: bool,
+    /// Local time of the decision, as `%Y-%m-%dT%H:%M:%S`.
+    pub decided_at: String,
+}
+
+impl Decision {
+    fn key(&self) -> String {
+        key(&self.artist, &self.title, self.album.as_deref(), &self.lyrics_sha256)
+    }
+}
+
+pub struct ReviewDecisions {
+    path: PathBuf,
+    decisions: Mutex<HashMap<String, Decision>>,
+}
+
+impl ReviewDecisions {
+    /// Loads the decisions stored at `path`, or starts without any if there are none or they
+    /// can't be read.
+    pub fn load(path: &Path) -> Self {
+        let decisions = read(path).unwrap_or_default();
+        Self { path: path.to_path_buf(), decisions: Mutex::new(by_key(decisions)) }
+    }
+
+    /// The decision made earlier about `lyrics` of the track, if any: true if they were accepted.
+    pub fn get(&self, artist: &str, title: &str, album: Option<&str>, lyrics: &str) -> Option<bool> {
+        let key = key(artist, title, album, &hash(lyrics));
+        self.decisions.lock().unwrap().get(&key).map(|decision| decision.accept)
+    }
+
+    /// Remembers that `lyrics` of the track were accepted or rejected, and saves the decisions.
+    pub fn record(&self, artist: &str, title: &str, album: Option<&str>, lyrics: &str, accept: bool) -> io::Result<()> {
+        let decision = Decision {
+            artist: artist.to_string(),
+            title: title.to_string(),
+            album: album.map(str::to_string),
+            lyrics_sha256: hash(lyrics),
+            accept,
+            decided_at: Local::now().format("%Y-%m-%dT%H:%M:%S").to_string(),
+        };
+        let mut decisions = self.decisions.lock().unwrap();
+        decisions.insert(decision.key(), decision);
+        write(&self.path, &decisions)
+    }
+
+    pub fn len(&self) -> usize {
+        self.decisions.lock().unwrap().len()
+    }
+
+    /// Writes every decision to `path`, to be imported elsewhere.
+    pub fn export(&self, path: &Path) -> io::Result<()> {
+        write(path, &self.decisions.lock().unwrap())
+    }
+
+    /// Adds the decisions exported to `path`, unless one made here about the same lyrics is newer,
+    /// and saves them. Returns the number of decisions added or replaced.
+    pub fn import(&self, path: &Path) -> io::Result<usize> {
+        let imported = read(path)?;
+        let mut decisions = self.decisions.lock().unwrap();
+        let mut changed = 0;
+        for (key, decision) in by_key(imported) {
+            if decisions.get(&key).is_none_or(|existing| existing.decided_at <= decision.decided_at) {
+                decisions.insert(key, decision);
+                changed += 1;
+            }
+        }
+        write(&self.path, &decisions)?;
+        Ok(changed)
+    }
+}
+
+fn read(path: &Path) -> io::Result<Vec<Decision>> {
+    Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+}
+
+/// Writes `decisions` to `path` as a JSON array, oldest first.
+fn write(path: &Path, decisions: &HashMap<String, Decision>) -> io::Result<()> {
+    let mut decisions: Vec<&Decision> = decisions.values().collect();
+    decisions.sort_by(|a, b| a.decided_at.cmp(&b.decided_at));
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    // Written next to the file and renamed over it, so a crash can't leave half a file
+    let temp = path.with_extension("json.tmp");
+    fs::write(&temp, serde_json::to_string_pretty(&decisions)?)?;
+    fs::rename(&temp, path)
+}
+
+fn by_key(decisions: Vec<Decision>) -> HashMap<String, Decision> {
+    decisions.into_iter().map(|decision| (decision.key(), decision)).collect()
+}
+
+fn hash(lyrics: &str) -> String {
+    format!("{:x}", Sha256::digest(lyrics.as_bytes()))
+}
+
+/// Key of a decision, ignoring case and surrounding whitespace of the artist, title and album.
+fn key(artist: &str, title: &str, album: Option<&str>, lyrics_sha256: &str) -> String {
+    let normalize = |text: &str| text.trim().to_lowercase();
+    let album = album.map_or(String::new(), normalize);
+    format!("{}\u{1f}{}\u{1f}{}\u{1f}{}", normalize(artist), normalize(title), album, lyrics_sha256)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn imports_keep_the_newer_decision() {
+        let dir = std::env::temp_dir().join(format!("review-decisions-{}", std::process::id()));
+        let (laptop, desktop) = (ReviewDecisions::load(&dir.join("laptop.json")), ReviewDecisions::load(&dir.join("desktop.json")));
+        desktop.record("Artist", "Song", None, "[00:01.00]Line", false).unwrap();
+        laptop.record(" artist", "SONG ", None, "[00:01.00]Line", true).unwrap();
+        laptop.record("Artist", "Other", Some("Album"), "Other lyrics", true).unwrap();
+        let exported = dir.join("exported.json");
+        laptop.export(&exported).unwrap();
+
+        assert_eq!(desktop.import(&exported).unwrap(), 2);
+        assert_eq!(desktop.get("Artist", "Song", None, "[00:01.00]Line"), Some(true));
+        assert_eq!(desktop.get("Artist", "Song", None, "[00:01.00]Other line"), None);
+        assert_eq!(ReviewDecisions::load(&dir.join("desktop.json")).len(), 2);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
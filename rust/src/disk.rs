@@ -0,0 +1,176 @@
+//! Checks that the target volume has room for the files a run writes, bounds the memory used by
+//! tag rewrites, and serializes rewrites on devices that slow down under parallel access.
+
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread;
+use std::time::Duration;
+
+use crate::{RunContext, CANCEL_POLL_INTERVAL};
+
+/// How often a paused write re-checks the free space.
+const RECHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Free space on the volume holding `path` (a file or a folder), or None if it cannot be determined.
+pub fn free_space(path: &Path) -> Option<u64> {
+    let dir = if path.is_dir() { path } else { path.parent().unwrap_or(path) };
+    fs4::available_space(dir).ok()
+}
+
+/// Blocks until the volume holding `path` has `min_free + needed` bytes available.
+///
+/// While waiting, `low_space` is set so the UI can show a warning, and a message is logged once.
+/// Returns false if the run was cancelled while waiting. If the free space cannot be determined
+/// the write goes ahead.
+pub fn wait_for_space(path: &Path, needed: u64, min_free: u64, low_space: &AtomicBool, run: &RunContext) -> bool {
+    let mut warned = false;
+    while free_space(path).is_some_and(|free| free < min_free.saturating_add(needed)) {
+        if !warned {
+            run.logs.lock().unwrap().push(format!(
+                "⚠ Low disk space near {}: pausing writes until at least {} MB are free",
+                path.display(),
+                (min_free + needed) / 1_000_000
+            ));
+            warned = true;
+        }
+        low_space.store(true, Ordering::SeqCst);
+        for _ in 0..(RECHECK_INTERVAL.as_millis() / CANCEL_POLL_INTERVAL.as_millis()) {
+            if run.cancel.is_cancelled() {
+                return false;
+            }
+            thread::sleep(CANCEL_POLL_INTERVAL);
+        }
+    }
+    if warned {
+        low_space.store(false, Ordering::SeqCst);
+        run.logs.lock().unwrap().push("▶ Enough disk space again, resuming writes".to_string());
+    }
+    !run.cancel.is_cancelled()
+}
+
+/// Flushes the contents of the file at `path` to disk.
+pub fn sync_file(path: &Path) -> io::Result<()> {
+    // Windows only flushes handles that were opened for writing
+    OpenOptions::new().write(true).open(path)?.sync_all()
+}
+
+/// Flushes the directory entry of a newly created file, where the platform supports it.
+pub fn sync_parent_dir(path: &Path) {
+    if cfg!(unix) {
+        if let Some(parent) = path.parent() {
+            let _ = File::open(parent).and_then(|dir| dir.sync_all());
+        }
+    }
+}
+
+/// Caps the combined size of the audio files being rewritten at the same time.
+///
+/// Saving tags may load the whole file into memory, so several multi-hundred-MB FLACs written in
+/// parallel can exhaust the RAM of small machines. A file larger than the whole budget is still
+/// written, but only while no other rewrite is running.
+pub struct WriteBudget {
+    limit: u64,
+    used: Mutex<u64>,
+    released: Condvar,
+}
+
+impl WriteBudget {
+    pub fn new(limit: u64) -> Self {
+        Self { limit, used: Mutex::new(0), released: Condvar::new() }
+    }
+
+    /// Waits until `bytes` fit in the budget. Returns None if the run was cancelled first.
+    pub fn acquire(&self, bytes: u64, run: &RunContext) -> Option<BudgetGuard<'_>> {
+        let mut used = self.used.lock().unwrap();
+        while *used > 0 && *used + bytes > self.limit {
+            if run.cancel.is_cancelled() {
+                return None;
+            }
+            used = self.released.wait_timeout(used, CANCEL_POLL_INTERVAL).unwrap().0;
+        }
+        *used += bytes;
+        Some(BudgetGuard { budget: self, bytes })
+    }
+}
+
+/// Returns its share of the `WriteBudget` when dropped.
+pub struct BudgetGuard<'a> {
+    budget: &'a WriteBudget,
+    bytes: u64,
+}
+
+impl Drop for BudgetGuard<'_> {
+    fn drop(&mut self) {
+        *self.budget.used.lock().unwrap() -= self.bytes;
+        self.budget.released.notify_all();
+    }
+}
+
+/// One lock per device that tag rewrites are serialized on.
+///
+/// Rewriting several files at once on a spinning disk or a network share makes the drive seek
+/// back and forth, which is slower than writing them one after another. Devices known to be
+/// solid state are left out and may be written in parallel.
+#[derive(Default)]
+pub struct DeviceLocks {
+    locks: Mutex<HashMap<String, Option<Arc<Mutex<()>>>>>,
+}
+
+impl DeviceLocks {
+    /// Returns the lock of the device holding `path`, or None if it may be written in parallel.
+    pub fn for_path(&self, path: &Path) -> Option<Arc<Mutex<()>>> {
+        let device = device_id(path)?;
+        let mut locks = self.locks.lock().unwrap();
+        locks
+            .entry(device.clone())
+            .or_insert_with(|| (!is_solid_state(&device)).then(Arc::default))
+            .clone()
+    }
+}
+
+/// Holds `lock` if there is one; used with `DeviceLocks::for_path`.
+pub fn lock_device(lock: &Option<Arc<Mutex<()>>>) -> Option<MutexGuard<'_, ()>> {
+    lock.as_ref().map(|lock| lock.lock().unwrap())
+}
+
+/// Identifies the device holding `path`.
+#[cfg(unix)]
+fn device_id(path: &Path) -> Option<String> {
+    use std::os::unix::fs::MetadataExt;
+    std::fs::metadata(path).ok().map(|meta| meta.dev().to_string())
+}
+
+/// Identifies the drive or network share holding `path` by its prefix, such as `C:` or `\\server\share`.
+#[cfg(not(unix))]
+fn device_id(path: &Path) -> Option<String> {
+    match path.components().next()? {
+        std::path::Component::Prefix(prefix) => Some(prefix.as_os_str().to_string_lossy().to_lowercase()),
+        _ => None,
+    }
+}
+
+/// Returns true if the block device `device` (as returned by `device_id`) is known not to be a
+/// spinning disk. Network shares have no block device and count as slow.
+#[cfg(target_os = "linux")]
+fn is_solid_state(device: &str) -> bool {
+    let Ok(dev) = device.parse::<u64>() else {
+        return false;
+    };
+    let major = ((dev >> 32) & 0xffff_f000) | ((dev >> 8) & 0xfff);
+    let minor = ((dev >> 12) & 0xffff_ff00) | (dev & 0xff);
+    let sysfs = std::path::PathBuf::from(format!("/sys/dev/block/{}:{}", major, minor));
+    // Partitions keep the queue settings in their parent disk's directory
+    [sysfs.join("queue/rotational"), sysfs.join("../queue/rotational")]
+        .iter()
+        .find_map(|path| std::fs::read_to_string(path).ok())
+        .is_some_and(|rotational| rotational.trim() == "0")
+}
+
+#[cfg(not(target_os = "linux"))]
+fn is_solid_state(_device: &str) -> bool {
+    false
+}
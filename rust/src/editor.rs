@@ -0,0 +1,78 @@
+//! Editor for `.lrc` files, mostly for lyrics that are a second or two out of sync.
+//!
+//! All timestamps can be moved by the same amount, either by rewriting them or by setting the
+//! `[offset:]` tag that players apply on top of them. Lines can be edited by hand, and the file is
+//! written back on save.
+
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use eframe::egui;
+
+use crate::{labelled, lrc};
+
+pub struct LrcEditor {
+    pub path: PathBuf,
+    text: String,
+    /// Milliseconds to move the lyrics by, later for positive values.
+    shift_ms: i64,
+    /// Move the lyrics by changing the `[offset:]` tag instead of every timestamp.
+    offset_tag: bool,
+    /// Set when the text changed since it was opened or saved.
+    modified: bool,
+    /// Outcome of the last save.
+    status: Option<String>,
+}
+
+impl LrcEditor {
+    pub fn open(path: PathBuf) -> io::Result<Self> {
+        let text = fs::read_to_string(&path)?;
+        Ok(Self { path, text, shift_ms: 0, offset_tag: false, modified: false, status: None })
+    }
+
+    /// Shows the editor window. Returns false once it was closed.
+    pub fn show(&mut self, ctx: &egui::Context) -> bool {
+        let mut open = true;
+        egui::Window::new("Edit .lrc file").open(&mut open).collapsible(false).show(ctx, |ui| {
+            ui.label(self.path.display().to_string());
+            ui.horizontal(|ui| {
+                labelled(ui, "Move lyrics by (ms):", egui::DragValue::new(&mut self.shift_ms).clamp_range(-60_000..=60_000).speed(10))
+                    .on_hover_text("Negative values show the lyrics earlier");
+                ui.radio_value(&mut self.offset_tag, false, "by rewriting the timestamps");
+                ui.radio_value(&mut self.offset_tag, true, "with an [offset:] tag");
+                if ui.button("Apply").clicked() && self.shift_ms != 0 {
+                    self.text = match self.offset_tag {
+                        // Players show lyrics earlier by the offset, so it moves the other way
+                        true => lrc::with_offset(&self.text, lrc::offset(&self.text) - self.shift_ms),
+                        false => lrc::shift_timestamps(&self.text, self.shift_ms),
+                    };
+                    self.modified = true;
+                }
+            });
+            egui::ScrollArea::vertical().id_source("lrc_editor").max_height(400.0).show(ui, |ui| {
+                let edit = egui::TextEdit::multiline(&mut self.text).code_editor().desired_rows(20).desired_width(f32::INFINITY);
+                if ui.add(edit).changed() {
+                    self.modified = true;
+                }
+            });
+            ui.horizontal(|ui| {
+                if ui.add_enabled(self.modified, egui::Button::new("Save")).clicked() {
+                    self.status = Some(match fs::write(&self.path, &self.text) {
+                        Ok(()) => {
+                            self.modified = false;
+                            "💾 Saved".to_string()
+                        }
+                        Err(e) => format!("❌ Failed to save: {}", e),
+                    });
+                }
+                if self.modified {
+                    ui.label("Unsaved changes");
+                } else if let Some(status) = &self.status {
+                    ui.label(status);
+                }
+            });
+        });
+        open
+    }
+}
@@ -0,0 +1,124 @@
+//! Classification of the reasons a file did not get lyrics, and the end-of-run summary.
+
+use std::collections::BTreeMap;
+use std::io;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+/// Why a file ended up without lyrics.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Failure {
+    /// The file has no usable title or artist.
+    NoMetadata,
+    /// The provider had no lyrics for the track.
+    NoMatch,
+    /// The provider knows the track is an instrumental.
+    Instrumental,
+    /// The request failed or the provider answered with an error.
+    Network,
+    /// The provider answered with HTTP 429 until the retries ran out.
+    RateLimited,
+    /// The provider's answer could not be read.
+    Parse,
+    /// The file ran out of its time budget.
+    TimedOut,
+    /// The run was stopped before the file was done.
+    Cancelled,
+    /// The file is read-only, locked by another program, or not accessible.
+    Permission,
+    /// Writing the `.lrc` file or the tags failed for another reason.
+    Write,
+}
+
+impl Failure {
+    pub const ALL: [Failure; 10] = [
+        Failure::NoMetadata,
+        Failure::NoMatch,
+        Failure::Instrumental,
+        Failure::Network,
+        Failure::RateLimited,
+        Failure::Parse,
+        Failure::TimedOut,
+        Failure::Cancelled,
+        Failure::Permission,
+        Failure::Write,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Failure::NoMetadata => "no metadata",
+            Failure::NoMatch => "no match",
+            Failure::Instrumental => "instrumental",
+            Failure::Network => "network",
+            Failure::RateLimited => "rate limited",
+            Failure::Parse => "unreadable answer",
+            Failure::TimedOut => "timed out",
+            Failure::Cancelled => "cancelled",
+            Failure::Permission => "locked or read-only",
+            Failure::Write => "write error",
+        }
+    }
+
+    /// Whether the lookup may succeed if it is tried again a little later.
+    pub fn is_transient(self) -> bool {
+        matches!(self, Failure::Network | Failure::RateLimited)
+    }
+
+    /// Whether something went wrong, rather than the track having no lyrics to find.
+    pub fn is_error(self) -> bool {
+        !matches!(self, Failure::NoMetadata | Failure::NoMatch | Failure::Instrumental | Failure::Cancelled)
+    }
+
+    /// Classifies an IO error that occurred while writing a file.
+    pub fn from_io(error: &io::Error) -> Self {
+        // Windows reports files opened by another program as sharing (32) or lock (33) violations
+        if error.kind() == io::ErrorKind::PermissionDenied || matches!(error.raw_os_error(), Some(32 | 33) if cfg!(windows)) {
+            Failure::Permission
+        } else {
+            Failure::Write
+        }
+    }
+
+    /// Classifies an error returned by lofty while reading or saving tags.
+    pub fn from_lofty(error: &lofty::LoftyError) -> Self {
+        match error.kind() {
+            lofty::error::ErrorKind::Io(error) => Failure::from_io(error),
+            _ => Failure::Write,
+        }
+    }
+}
+
+/// Number of failures of each kind during a run.
+#[derive(Default)]
+pub struct FailureCounts {
+    counts: Mutex<BTreeMap<Failure, usize>>,
+}
+
+impl FailureCounts {
+    pub fn record(&self, failure: Failure) {
+        *self.counts.lock().unwrap().entry(failure).or_default() += 1;
+    }
+
+    pub fn counts(&self) -> BTreeMap<Failure, usize> {
+        self.counts.lock().unwrap().clone()
+    }
+}
+
+/// Formats failure counts as e.g. "214 no match, 12 network, 3 locked or read-only",
+/// or returns None if nothing failed.
+pub fn describe_counts(counts: &BTreeMap<Failure, usize>) -> Option<String> {
+    if counts.is_empty() {
+        return None;
+    }
+    let mut counts: Vec<_> = counts.iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(a.1));
+    Some(
+        counts
+            .iter()
+            .map(|(failure, count)| format!("{} {}", count, failure.label()))
+            .collect::<Vec<_>>()
+            .join(", "),
+    )
+}
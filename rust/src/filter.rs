@@ -0,0 +1,124 @@
+//! Filters that keep files out of a scan, for libraries that hold more than music: audiobooks,
+//! podcasts and short sound effects.
+//!
+//! Paths are left out by globs or regexes, files by extension, and tracks by length. Path
+//! patterns are matched against the path relative to the library folder, with `/` as separator,
+//! and globs without a `/` also against every single folder and file name, so `Audiobooks`
+//! leaves out that folder wherever it is.
+
+use std::path::{Component, Path};
+use std::time::Duration;
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use lofty::{AudioFile, ParseOptions, Probe};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// Prefix of exclude patterns that are regexes instead of globs.
+pub const REGEX_PREFIX: &str = "re:";
+
+/// Which files a scan leaves out. Kept per profile in the config file.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct ScanFilter {
+    /// Globs such as `*Podcast*` or `Audiobooks/**`, or regexes prefixed with `re:`.
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    /// Comma-separated extensions of the files scanned, such as `mp3, flac`. All supported
+    /// formats if empty.
+    #[serde(default)]
+    pub extensions: String,
+    /// Tracks shorter than this many seconds are left out, if not zero.
+    #[serde(default)]
+    pub min_duration_secs: u64,
+    /// Tracks longer than this many seconds are left out, if not zero.
+    #[serde(default)]
+    pub max_duration_secs: u64,
+}
+
+/// Checks whether `pattern` is a valid exclude pattern, returning the error if it isn't.
+pub fn check_pattern(pattern: &str) -> Result<(), String> {
+    match pattern.strip_prefix(REGEX_PREFIX) {
+        Some(regex) => Regex::new(regex).map(drop).map_err(|e| e.to_string()),
+        None => Glob::new(pattern).map(drop).map_err(|e| e.to_string()),
+    }
+}
+
+/// A `ScanFilter` ready to be applied to many files.
+pub struct ScanRules {
+    globs: GlobSet,
+    regexes: Vec<Regex>,
+    extensions: Vec<String>,
+    min_duration: Option<Duration>,
+    max_duration: Option<Duration>,
+}
+
+impl ScanRules {
+    /// Compiles `filter`. Invalid patterns, which can only come from editing the config file by
+    /// hand, are ignored.
+    pub fn new(filter: &ScanFilter) -> Self {
+        let mut globs = GlobSetBuilder::new();
+        let mut regexes = Vec::new();
+        for pattern in filter.exclude.iter().map(|pattern| pattern.trim()) {
+            match pattern.strip_prefix(REGEX_PREFIX) {
+                Some(regex) => regexes.extend(Regex::new(regex).ok()),
+                None => {
+                    if let Ok(glob) = Glob::new(pattern) {
+                        globs.add(glob);
+                    }
+                }
+            }
+        }
+        let extensions = filter
+            .extensions
+            .split(',')
+            .map(|ext| ext.trim().trim_start_matches('.').to_lowercase())
+            .filter(|ext| !ext.is_empty())
+            .collect();
+        let limit = |secs| (secs > 0).then(|| Duration::from_secs(secs));
+        Self {
+            globs: globs.build().unwrap_or_else(|_| GlobSet::empty()),
+            regexes,
+            extensions,
+            min_duration: limit(filter.min_duration_secs),
+            max_duration: limit(filter.max_duration_secs),
+        }
+    }
+
+    /// Whether the audio file at `path`, found in the library folder `root`, is scanned. The
+    /// file is read for its length only if a length limit is set; files whose length can't be
+    /// read are kept.
+    pub fn accepts(&self, path: &Path, ext: &str, root: &Path) -> bool {
+        if !self.extensions.is_empty() && !self.extensions.iter().any(|allowed| allowed == ext) {
+            return false;
+        }
+        if self.is_excluded(path.strip_prefix(root).unwrap_or(path)) {
+            return false;
+        }
+        if self.min_duration.is_none() && self.max_duration.is_none() {
+            return true;
+        }
+        match duration(path) {
+            Some(duration) => {
+                self.min_duration.is_none_or(|min| duration >= min) && self.max_duration.is_none_or(|max| duration <= max)
+            }
+            None => true,
+        }
+    }
+
+    fn is_excluded(&self, relative: &Path) -> bool {
+        let text = relative.to_string_lossy().replace('\\', "/");
+        if self.globs.is_match(&text) || self.regexes.iter().any(|regex| regex.is_match(&text)) {
+            return true;
+        }
+        relative.components().any(|component| match component {
+            Component::Normal(name) => self.globs.is_match(Path::new(name)),
+            _ => false,
+        })
+    }
+}
+
+/// Length of the audio file at `path`, read from its header.
+fn duration(path: &Path) -> Option<Duration> {
+    let tagged_file = Probe::open(path).ok()?.options(ParseOptions::new().read_properties(true)).read().ok()?;
+    Some(tagged_file.properties().duration()).filter(|duration| !duration.is_zero())
+}
@@ -0,0 +1,169 @@
+//! Genius, which covers a lot of older and regional music that lrclib lacks.
+//!
+//! Genius only has plain lyrics, and its API doesn't return them: the API finds the song, and the
+//! lyrics are read from the song's web page. Using the API needs a free client access token.
+
+use serde::Deserialize;
+
+use crate::failure::Failure;
+use crate::network::http_client;
+use crate::providers::{self, LyricsProvider, Query};
+use crate::{provider_request, settle, CancellationToken, LyricsResult, RunContext};
+
+const GENIUS: &str = "genius";
+
+pub struct Genius {
+    token: String,
+}
+
+impl Genius {
+    /// The provider, or None if `token` is empty.
+    pub fn new(token: &str) -> Option<Self> {
+        let token = token.trim();
+        (!token.is_empty()).then(|| Self { token: token.to_string() })
+    }
+}
+
+#[derive(Deserialize)]
+struct SearchResponse {
+    response: SearchHits,
+}
+
+#[derive(Deserialize)]
+struct SearchHits {
+    hits: Vec<SearchHit>,
+}
+
+#[derive(Deserialize)]
+struct SearchHit {
+    result: Song,
+}
+
+#[derive(Deserialize)]
+struct Song {
+    id: u64,
+    title: String,
+    url: String,
+    primary_artist: Artist,
+}
+
+#[derive(Deserialize)]
+struct Artist {
+    name: String,
+}
+
+impl LyricsProvider for Genius {
+    fn name(&self) -> &'static str {
+        GENIUS
+    }
+
+    fn has_synced_lyrics(&self) -> bool {
+        false
+    }
+
+    /// Searches for "artist title" and takes the song that matches the query best, see
+    /// `providers::best_result`, as the search also returns songs that merely mention the words.
+    /// Queries without an artist aren't searched, as any song of the same name would do.
+    fn fetch(&self, query: &Query, run: &RunContext, cancel: &CancellationToken) -> Result<Option<LyricsResult>, Failure> {
+        if query.artist.trim().is_empty() {
+            return Ok(None);
+        }
+        let search = http_client()
+            .get("https://api.genius.com/search")
+            .query(&[("q", format!("{} {}", query.artist, query.title))])
+            .bearer_auth(&self.token);
+        let parse = |body: &[u8]| serde_json::from_slice::<SearchResponse>(body).ok();
+        let Some(hits) = settle(GENIUS, provider_request(GENIUS, search, parse, run, cancel)?, run)? else {
+            return Ok(None);
+        };
+        let songs: Vec<Song> = hits.response.hits.into_iter().map(|hit| hit.result).collect();
+        let candidates = songs.iter().map(|song| LyricsResult {
+            id: Some(song.id),
+            track_name: Some(song.title.clone()),
+            artist_name: Some(song.primary_artist.name.clone()),
+            album_name: None,
+            duration: None,
+            synced_lyrics: None,
+            plain_lyrics: None,
+            instrumental: false,
+        });
+        let Some(mut result) = providers::best_result(GENIUS, candidates.collect(), query, run) else {
+            return Ok(None);
+        };
+        let Some(song) = songs.iter().find(|song| Some(song.id) == result.id) else {
+            return Ok(None);
+        };
+        let page = http_client().get(&song.url);
+        result.plain_lyrics = settle(GENIUS, provider_request(GENIUS, page, |body| Some(String::from_utf8_lossy(body).into_owned()), run, cancel)?, run)?
+            .and_then(|html| lyrics_from_page(&html));
+        Ok(result.plain_lyrics.is_some().then_some(result))
+    }
+}
+
+/// Reads the lyrics from a Genius song page, where they are split over several
+/// `data-lyrics-container` elements with `<br>` between the lines.
+fn lyrics_from_page(html: &str) -> Option<String> {
+    const CONTAINER: &str = "data-lyrics-container=\"true\"";
+    let mut lyrics = String::new();
+    let mut rest = html;
+    while let Some(start) = rest.find(CONTAINER) {
+        let body = &rest[start..];
+        let body = &body[body.find('>')? + 1..];
+        let end = element_end(body)?;
+        if !lyrics.is_empty() {
+            lyrics.push('\n');
+        }
+        lyrics.push_str(&text_of(&body[..end]));
+        rest = &body[end..];
+    }
+    let lyrics = lyrics.trim().to_string();
+    (!lyrics.is_empty()).then_some(lyrics)
+}
+
+/// Index of the `</div>` closing the element whose contents start at `html`.
+fn element_end(html: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut index = 0;
+    while let Some(offset) = html[index..].find("<") {
+        index += offset;
+        let tag = &html[index..];
+        if tag.starts_with("</div") {
+            if depth == 0 {
+                return Some(index);
+            }
+            depth -= 1;
+        } else if tag.starts_with("<div") {
+            depth += 1;
+        }
+        index += 1;
+    }
+    None
+}
+
+/// The text of an HTML fragment, with `<br>` as line breaks. Nested `<div>`s hold annotations and
+/// headers rather than lyrics and are left out.
+fn text_of(html: &str) -> String {
+    let mut text = String::new();
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        let tag = &rest[start..];
+        let Some(tag_end) = tag.find('>') else {
+            break;
+        };
+        rest = &tag[tag_end + 1..];
+        if tag.starts_with("<br") {
+            text.push('\n');
+        } else if tag.starts_with("<div") && !tag[..tag_end].ends_with('/') {
+            let end = element_end(rest).unwrap_or(rest.len());
+            rest = rest[end..].strip_prefix("</div>").unwrap_or(&rest[end..]);
+        }
+    }
+    text.push_str(rest);
+    text.replace("&amp;", "&")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#x27;", "'")
+        .replace("&#39;", "'")
+}
@@ -0,0 +1,89 @@
+//! History of past runs.
+//!
+//! A one-line summary of every run is appended to `history.jsonl` in the data directory, so the
+//! GUI can list past runs, show their failures again from the run manifest and re-run them with
+//! the same settings.
+
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::failure::Failure;
+use crate::manifest::{self, Outcome, RunDelta, RunManifest};
+use crate::{OutputMode, PipelineConfig};
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RunSummary {
+    pub started_at: String,
+    pub folder: PathBuf,
+    pub mode: OutputMode,
+    pub config: PipelineConfig,
+    pub scanned: usize,
+    pub written: usize,
+    pub failures: BTreeMap<Failure, usize>,
+    pub cancelled: bool,
+    /// The run's manifest, if it could be saved.
+    pub manifest: Option<PathBuf>,
+}
+
+/// Appends `summary` to the history file at `path`.
+pub fn append(path: &Path, summary: &RunSummary) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut line = serde_json::to_string(summary)?;
+    line.push('\n');
+    OpenOptions::new().create(true).append(true).open(path)?.write_all(line.as_bytes())
+}
+
+/// Reads the history file at `path`, oldest run first. Unreadable lines are skipped.
+pub fn load(path: &Path) -> Vec<RunSummary> {
+    let Ok(file) = File::open(path) else {
+        return Vec::new();
+    };
+    BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str(&line).ok())
+        .collect()
+}
+
+/// Reads the files that failed during a run, with their reasons, from its manifest.
+pub fn failed_files(manifest: &Path) -> io::Result<Vec<(PathBuf, Failure)>> {
+    let manifest: RunManifest = serde_json::from_str(&fs::read_to_string(manifest)?)?;
+    Ok(manifest
+        .files
+        .into_iter()
+        .filter_map(|entry| match entry.outcome {
+            Outcome::Failed { reason } => Some((entry.file, reason)),
+            Outcome::Written { .. } | Outcome::Kept { .. } | Outcome::Skipped => None,
+        })
+        .collect())
+}
+
+/// Reads the groups of files with identical lyrics in a run from its manifest.
+pub fn duplicate_files(manifest: &Path) -> io::Result<Vec<Vec<PathBuf>>> {
+    let manifest: RunManifest = serde_json::from_str(&fs::read_to_string(manifest)?)?;
+    Ok(manifest::duplicate_lyrics(&manifest.files))
+}
+
+/// The manifest of the last completed run of `folder` in `mode`, from the history file at `path`.
+///
+/// Cancelled runs are passed over, since files they never reached would look newly covered.
+pub fn previous_manifest(path: &Path, folder: &Path, mode: OutputMode) -> Option<PathBuf> {
+    load(path)
+        .into_iter()
+        .rev()
+        .filter(|summary| summary.folder == folder && summary.mode == mode && !summary.cancelled)
+        .find_map(|summary| summary.manifest)
+}
+
+/// Compares the run of the `current` manifest with the run of the `previous` one.
+pub fn compare_runs(previous: &Path, current: &Path) -> io::Result<RunDelta> {
+    let previous: RunManifest = serde_json::from_str(&fs::read_to_string(previous)?)?;
+    let current: RunManifest = serde_json::from_str(&fs::read_to_string(current)?)?;
+    Ok(manifest::compare(&previous.files, &current.files))
+}
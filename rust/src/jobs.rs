@@ -0,0 +1,95 @@
+//! Crash-recoverable journal of the files processed by a run.
+//!
+//! The journal is a JSON-lines file that records which folder and mode a run was started with
+//! and every file whose processing has completed. It is removed when a run finishes; if the app
+//! crashes or is closed mid-run, the next launch finds it and offers to resume, re-queuing every
+//! file that was not completed.
+
+use std::collections::HashSet;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::OutputMode;
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum JournalEntry {
+    Started { folder: PathBuf, mode: OutputMode },
+    Done(PathBuf),
+}
+
+/// A run that was started but never finished.
+pub struct InterruptedRun {
+    pub folder: PathBuf,
+    pub mode: OutputMode,
+    pub done: HashSet<PathBuf>,
+}
+
+impl InterruptedRun {
+    /// Reads the journal at `path`, if one was left behind by an unfinished run.
+    pub fn load(path: &Path) -> Option<Self> {
+        let file = File::open(path).ok()?;
+        let mut lines = BufReader::new(file).lines().map_while(Result::ok);
+        let Ok(JournalEntry::Started { folder, mode }) = serde_json::from_str(&lines.next()?) else {
+            return None;
+        };
+        let done = lines
+            .filter_map(|line| match serde_json::from_str(&line) {
+                Ok(JournalEntry::Done(path)) => Some(path),
+                _ => None,
+            })
+            .collect();
+        Some(Self { folder, mode, done })
+    }
+}
+
+/// The journal of the run in progress.
+pub struct JobJournal {
+    path: PathBuf,
+    file: Mutex<File>,
+    done: HashSet<PathBuf>,
+}
+
+impl JobJournal {
+    /// Starts a new journal at `path`, replacing any previous one.
+    pub fn start(path: &Path, folder: &Path, mode: OutputMode) -> io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let journal = Self { path: path.to_path_buf(), file: Mutex::new(File::create(path)?), done: HashSet::new() };
+        journal.append(&JournalEntry::Started { folder: folder.to_path_buf(), mode })?;
+        Ok(journal)
+    }
+
+    /// Continues the journal of an interrupted run, keeping its completed files.
+    pub fn resume(path: &Path, interrupted: InterruptedRun) -> io::Result<Self> {
+        let file = OpenOptions::new().append(true).open(path)?;
+        Ok(Self { path: path.to_path_buf(), file: Mutex::new(file), done: interrupted.done })
+    }
+
+    /// Returns true if `path` was already completed before the run was interrupted.
+    pub fn is_done(&self, path: &Path) -> bool {
+        self.done.contains(path)
+    }
+
+    /// Records that processing of `path` has completed.
+    pub fn complete(&self, path: &Path) {
+        let _ = self.append(&JournalEntry::Done(path.to_path_buf()));
+    }
+
+    /// Removes the journal once the run has finished.
+    pub fn finish(self) {
+        drop(self.file);
+        let _ = fs::remove_file(&self.path);
+    }
+
+    fn append(&self, entry: &JournalEntry) -> io::Result<()> {
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+        self.file.lock().unwrap().write_all(line.as_bytes())
+    }
+}
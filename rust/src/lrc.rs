@@ -0,0 +1,337 @@
+//! Helpers for reading LRC lyrics.
+
+use std::time::Duration;
+
+/// Name this app stamps into the `[re:]` header of the `.lrc` files it writes.
+const TOOL: &str = "LyricsDownloader";
+
+/// Lyrics written for instrumentals when they are marked. The timed line makes it a `.lrc` file
+/// and gives players something to show.
+pub const INSTRUMENTAL: &str = "[au: instrumental]\n[00:00.00]♪ Instrumental ♪\n";
+
+/// Returns whether `lyrics` contain at least one line with a `[mm:ss.xx]` timestamp.
+pub fn is_synced(lyrics: &str) -> bool {
+    lyrics.lines().any(|line| strip_timestamps(line).len() < line.trim_start().len())
+}
+
+/// Number of characters of lyric text, ignoring timestamps and whitespace.
+pub fn text_len(lyrics: &str) -> usize {
+    lyrics
+        .lines()
+        .map(|line| strip_timestamps(line).chars().filter(|c| !c.is_whitespace()).count())
+        .sum()
+}
+
+/// Splits lyric lines longer than `max_chars` characters at word boundaries.
+///
+/// Every part keeps the timestamps of the original line, so players show the parts one after
+/// another at the same time. Words longer than `max_chars` are split mid-word. Metadata tags such
+/// as `[ar:...]` are left alone.
+pub fn wrap_lines(lyrics: &str, max_chars: usize) -> String {
+    let mut wrapped = String::with_capacity(lyrics.len());
+    for line in lyrics.lines() {
+        let text = strip_timestamps(line);
+        if text.chars().count() <= max_chars || text.starts_with('[') {
+            wrapped.push_str(line);
+            wrapped.push('\n');
+            continue;
+        }
+        let prefix = &line[..line.len() - text.len()];
+        for part in split_words(text, max_chars) {
+            wrapped.push_str(prefix);
+            wrapped.push_str(&part);
+            wrapped.push('\n');
+        }
+    }
+    wrapped
+}
+
+/// Greedily packs the words of `text` into parts of at most `max_chars` characters.
+fn split_words(text: &str, max_chars: usize) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    for word in text.split_whitespace() {
+        let mut word: Vec<char> = word.chars().collect();
+        while word.len() > max_chars {
+            if !current.is_empty() {
+                parts.push(std::mem::take(&mut current));
+            }
+            parts.push(word.drain(..max_chars).collect());
+        }
+        if word.is_empty() {
+            continue;
+        }
+        let current_len = current.chars().count();
+        if current_len > 0 && current_len + 1 + word.len() > max_chars {
+            parts.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.extend(word);
+    }
+    if !current.is_empty() {
+        parts.push(current);
+    }
+    parts
+}
+
+/// Returns `line` without its leading timestamps. Metadata tags such as `[ar:...]` are kept.
+pub fn strip_timestamps(line: &str) -> &str {
+    let mut rest = line.trim_start();
+    while let Some((stamp, tail)) = rest.strip_prefix('[').and_then(|after| after.split_once(']')) {
+        if !is_timestamp(stamp) {
+            break;
+        }
+        rest = tail;
+    }
+    rest
+}
+
+/// Whether `stamp` (without brackets) is a time of the form `mm:ss` or `mm:ss.xx`.
+fn is_timestamp(stamp: &str) -> bool {
+    let Some((minutes, seconds)) = stamp.split_once(':') else {
+        return false;
+    };
+    !minutes.is_empty() && minutes.chars().all(|c| c.is_ascii_digit()) && seconds.parse::<f64>().is_ok()
+}
+
+/// Parses the timed lines of `lyrics` into `(milliseconds, text)` pairs sorted by time.
+///
+/// A line with several timestamps yields one pair per timestamp. Untimed lines and metadata
+/// tags are dropped.
+pub fn timed_lines(lyrics: &str) -> Vec<(u32, String)> {
+    let mut lines = Vec::new();
+    for line in lyrics.lines() {
+        let text = strip_timestamps(line);
+        let stamps = &line.trim_start()[..line.trim_start().len() - text.len()];
+        for stamp in stamps.split(']').filter_map(|stamp| stamp.strip_prefix('[')) {
+            if let Some(millis) = parse_timestamp(stamp) {
+                lines.push((millis, text.trim_end().to_string()));
+            }
+        }
+    }
+    lines.sort_by_key(|&(millis, _)| millis);
+    lines
+}
+
+/// Formats `(milliseconds, text)` pairs as LRC lines with `[mm:ss.xx]` timestamps.
+pub fn format_timed_lines(lines: &[(u32, String)]) -> String {
+    let mut lyrics = String::new();
+    for (millis, text) in lines {
+        lyrics.push_str(&format!("{}{}\n", format_timestamp(*millis), text));
+    }
+    lyrics
+}
+
+/// Formats milliseconds as a `[mm:ss.xx]` timestamp.
+fn format_timestamp(millis: u32) -> String {
+    let centis = millis / 10;
+    format!("[{:02}:{:02}.{:02}]", centis / 6000, centis / 100 % 60, centis % 100)
+}
+
+/// Moves every timestamp of `lyrics` by `shift_ms`, to later times for positive values. Times
+/// that would fall before the start are set to zero. Untimed lines and tags are kept as they are.
+pub fn shift_timestamps(lyrics: &str, shift_ms: i64) -> String {
+    let mut shifted = String::new();
+    for line in lyrics.lines() {
+        let text = strip_timestamps(line);
+        let stamps = &line.trim_start()[..line.trim_start().len() - text.len()];
+        for stamp in stamps.split(']').filter_map(|stamp| stamp.strip_prefix('[')) {
+            let millis = parse_timestamp(stamp).map_or(0, i64::from);
+            shifted.push_str(&format_timestamp((millis + shift_ms).clamp(0, u32::MAX as i64) as u32));
+        }
+        shifted.push_str(if stamps.is_empty() { line } else { text });
+        shifted.push('\n');
+    }
+    shifted
+}
+
+/// The `[offset:]` tag of `lyrics` in milliseconds, 0 if there is none.
+///
+/// Players show the lyrics earlier by the offset, so a positive offset moves them all forward.
+pub fn offset(lyrics: &str) -> i64 {
+    lyrics.lines().find_map(|line| line.trim().strip_prefix("[offset:")?.strip_suffix(']')?.trim().parse().ok()).unwrap_or(0)
+}
+
+/// Sets the `[offset:]` tag of `lyrics` to `offset_ms`, replacing an existing one, or removes
+/// it for an offset of zero. The tag goes first, with the other tags.
+pub fn with_offset(lyrics: &str, offset_ms: i64) -> String {
+    let mut tagged = match offset_ms {
+        0 => String::new(),
+        offset => format!("[offset:{:+}]\n", offset),
+    };
+    for line in lyrics.lines().filter(|line| !line.trim_start().starts_with("[offset:")) {
+        tagged.push_str(line);
+        tagged.push('\n');
+    }
+    tagged
+}
+
+/// Converts a timestamp (without brackets) to milliseconds.
+fn parse_timestamp(stamp: &str) -> Option<u32> {
+    if !is_timestamp(stamp) {
+        return None;
+    }
+    let (minutes, seconds) = stamp.split_once(':')?;
+    let seconds = minutes.parse::<f64>().ok()? * 60.0 + seconds.parse::<f64>().ok()?;
+    Some((seconds * 1000.0).round() as u32)
+}
+
+/// Prepends `[re:]`, `[ve:]` and `[by:]` header lines naming this app, its version and `source`.
+///
+/// Header lines of the same kind already in `lyrics` are replaced.
+pub fn with_header(lyrics: &str, source: &str) -> String {
+    let mut stamped = format!("[re:{}]\n[ve:{}]\n[by:{}]\n", TOOL, env!("CARGO_PKG_VERSION"), source);
+    for line in lyrics.lines().filter(|line| !is_header_line(line)) {
+        stamped.push_str(line);
+        stamped.push('\n');
+    }
+    stamped
+}
+
+/// Metadata of a track, written as LRC ID tags by `with_id_tags`.
+pub struct IdTags<'a> {
+    pub artist: Option<&'a str>,
+    pub title: Option<&'a str>,
+    pub album: Option<&'a str>,
+    pub length: Option<Duration>,
+}
+
+/// Prepends `[ar:]`, `[ti:]`, `[al:]` and `[length:]` lines for the fields of `tags` that are
+/// known, and a `[by:]` line naming `source`, so players that show LRC ID tags credit the right
+/// track and provider.
+///
+/// ID tags of the same kinds already in `lyrics` are replaced.
+pub fn with_id_tags(lyrics: &str, tags: &IdTags, source: &str) -> String {
+    let mut tagged = String::new();
+    let fields = [("ar", tags.artist), ("ti", tags.title), ("al", tags.album)];
+    for (tag, value) in fields {
+        if let Some(value) = value.map(str::trim).filter(|value| !value.is_empty()) {
+            // A "]" would end the tag early
+            tagged.push_str(&format!("[{}:{}]\n", tag, value.replace(']', ")")));
+        }
+    }
+    if let Some(length) = tags.length {
+        let seconds = length.as_secs();
+        tagged.push_str(&format!("[length:{:02}:{:02}]\n", seconds / 60, seconds % 60));
+    }
+    tagged.push_str(&format!("[by:{}]\n", source));
+    for line in lyrics.lines().filter(|line| !is_id_tag_line(line)) {
+        tagged.push_str(line);
+        tagged.push('\n');
+    }
+    tagged
+}
+
+/// Whether `lyrics` carry the `[re:]` header that `with_header` writes.
+pub fn is_own_output(lyrics: &str) -> bool {
+    lyrics.lines().any(|line| line.trim() == format!("[re:{}]", TOOL))
+}
+
+fn is_header_line(line: &str) -> bool {
+    let line = line.trim_start();
+    ["[re:", "[ve:", "[by:"].iter().any(|tag| line.starts_with(tag))
+}
+
+fn is_id_tag_line(line: &str) -> bool {
+    let line = line.trim_start();
+    ["[ar:", "[ti:", "[al:", "[length:", "[by:"].iter().any(|tag| line.starts_with(tag))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_of_exactly_max_chars_is_kept_whole() {
+        assert_eq!(wrap_lines("[00:01.00]abcde fghij\n", 11), "[00:01.00]abcde fghij\n");
+    }
+
+    #[test]
+    fn long_line_is_split_at_words_keeping_its_timestamps() {
+        let wrapped = wrap_lines("[00:01.00][00:30.00]one two three\n", 8);
+        assert_eq!(wrapped, "[00:01.00][00:30.00]one two\n[00:01.00][00:30.00]three\n");
+    }
+
+    #[test]
+    fn word_longer_than_max_chars_is_split_mid_word() {
+        assert_eq!(wrap_lines("[00:01.00]a abcdefgh\n", 4), "[00:01.00]a\n[00:01.00]abcd\n[00:01.00]efgh\n");
+    }
+
+    #[test]
+    fn untimed_long_line_is_split_too() {
+        assert_eq!(wrap_lines("one two three\n", 7), "one two\nthree\n");
+    }
+
+    #[test]
+    fn tags_are_not_wrapped() {
+        let tag = "[ar:An Artist With A Long Name]\n";
+        assert_eq!(wrap_lines(tag, 5), tag);
+    }
+
+    #[test]
+    fn negative_shift_is_clamped_to_zero() {
+        assert_eq!(shift_timestamps("[00:01.50]early\n[00:10.00]late\n", -2000), "[00:00.00]early\n[00:08.00]late\n");
+    }
+
+    #[test]
+    fn shift_moves_every_timestamp_of_a_line() {
+        assert_eq!(shift_timestamps("[00:01.00][01:59.50]chorus\n", 750), "[00:01.75][02:00.25]chorus\n");
+    }
+
+    #[test]
+    fn shift_keeps_tags_and_untimed_lines() {
+        let lyrics = "[ar:Artist]\nuntimed\n";
+        assert_eq!(shift_timestamps(lyrics, 1000), lyrics);
+    }
+
+    #[test]
+    fn offset_tag_is_replaced_and_removed_at_zero() {
+        let tagged = with_offset("[ar:Artist]\n[offset:+500]\n[00:01.00]line\n", -250);
+        assert_eq!(tagged, "[offset:-250]\n[ar:Artist]\n[00:01.00]line\n");
+        assert_eq!(offset(&tagged), -250);
+        assert_eq!(with_offset(&tagged, 0), "[ar:Artist]\n[00:01.00]line\n");
+        assert_eq!(offset("[00:01.00]line\n"), 0);
+    }
+
+    #[test]
+    fn line_with_several_timestamps_yields_a_line_for_each() {
+        let lines = timed_lines("[ti:Song]\n[00:05.00]verse\n[00:01.00][00:10.00]chorus \n");
+        let expected = [(1000, "chorus"), (5000, "verse"), (10000, "chorus")];
+        assert_eq!(lines, expected.map(|(millis, text)| (millis, text.to_string())));
+    }
+
+    #[test]
+    fn lines_at_the_same_time_keep_their_order() {
+        let lines = timed_lines("[00:02.00]later\n[00:01.00]first\n[00:01.00]second\n");
+        assert_eq!(lines, [(1000, "first"), (1000, "second"), (2000, "later")].map(|(millis, text)| (millis, text.to_string())));
+    }
+
+    #[test]
+    fn timed_lines_round_trip_through_format_timed_lines() {
+        let lines = vec![(0, String::new()), (1230, "first".to_string()), (61_990, "second".to_string()), (3_600_000, "hour".to_string())];
+        let formatted = format_timed_lines(&lines);
+        assert_eq!(formatted, "[00:00.00]\n[00:01.23]first\n[01:01.99]second\n[60:00.00]hour\n");
+        assert_eq!(timed_lines(&formatted), lines);
+    }
+
+    #[test]
+    fn timestamps_are_truncated_to_hundredths() {
+        assert_eq!(format_timestamp(12_349), "[00:12.34]");
+        assert_eq!(parse_timestamp("00:12.345"), Some(12_345));
+        assert_eq!(parse_timestamp("1:02"), Some(62_000));
+        assert_eq!(parse_timestamp("ar:Artist"), None);
+    }
+
+    #[test]
+    fn text_length_ignores_timestamps_and_whitespace() {
+        assert_eq!(text_len("[00:01.00][00:20.00] one two \n\nthree\n"), 11);
+    }
+
+    #[test]
+    fn only_timestamps_make_lyrics_synced() {
+        assert!(is_synced("[ar:Artist]\n[00:01.00]line\n"));
+        assert!(!is_synced("[ar:Artist]\n[ti:Song]\nplain line\n"));
+    }
+}
@@ -0,0 +1,191 @@
+//! Machine-readable manifest of everything a run did.
+//!
+//! One JSON file is written per run, listing every audio file that was looked at, where its
+//! lyrics were written, which provider they came from and a SHA-256 hash of their content, so
+//! auditing, undo and sync tooling can be built on top of it.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::failure::Failure;
+use crate::results::RunResults;
+use crate::OutputMode;
+
+#[derive(Serialize, Deserialize)]
+pub struct RunManifest {
+    pub app_version: String,
+    pub started_at: String,
+    pub finished_at: String,
+    pub folder: PathBuf,
+    pub mode: OutputMode,
+    pub cancelled: bool,
+    pub files: Vec<ManifestEntry>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub file: PathBuf,
+    #[serde(flatten)]
+    pub outcome: Outcome,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum Outcome {
+    /// Lyrics were written to `output`, which is the `.lrc` file or the audio file itself.
+    Written { output: PathBuf, provider: String, lyrics_sha256: String },
+    /// The file already had different lyrics in `output`, which were left in place.
+    Kept { output: PathBuf },
+    /// The file was left alone on purpose: its artist is marked as never fetch, or an import
+    /// run found no `.lrc` file for it.
+    Skipped,
+    Failed { reason: Failure },
+}
+
+/// Collects the entries of the run in progress.
+pub struct ManifestRecorder {
+    started_at: String,
+    entries: Mutex<Vec<ManifestEntry>>,
+    /// Table the entries are also shown in while the run is in progress.
+    results: Arc<RunResults>,
+}
+
+impl ManifestRecorder {
+    pub fn new(results: Arc<RunResults>) -> Self {
+        Self { started_at: now(), entries: Mutex::new(Vec::new()), results }
+    }
+
+    /// Remembers the title and artist read from `file`, to show them in the results table.
+    pub fn add_track(&self, file: &Path, title: Option<&str>, artist: Option<&str>) {
+        self.results.add_track(file, title, artist);
+    }
+
+    pub fn started_at(&self) -> &str {
+        &self.started_at
+    }
+
+    pub fn written(&self, file: &Path, output: &Path, provider: &str, lyrics: &str) {
+        self.push(file, Outcome::Written {
+            output: output.to_path_buf(),
+            provider: provider.to_string(),
+            lyrics_sha256: format!("{:x}", Sha256::digest(lyrics.as_bytes())),
+        });
+    }
+
+    pub fn kept(&self, file: &Path, output: &Path) {
+        self.push(file, Outcome::Kept { output: output.to_path_buf() });
+    }
+
+    pub fn skipped(&self, file: &Path) {
+        self.push(file, Outcome::Skipped);
+    }
+
+    pub fn failed(&self, file: &Path, reason: Failure) {
+        self.push(file, Outcome::Failed { reason });
+    }
+
+    /// Number of files left alone on purpose so far.
+    pub fn skipped_count(&self) -> usize {
+        self.entries.lock().unwrap().iter().filter(|entry| matches!(entry.outcome, Outcome::Skipped)).count()
+    }
+
+    /// Groups of files written so far whose lyrics are identical, see `duplicate_lyrics`.
+    pub fn duplicates(&self) -> Vec<Vec<PathBuf>> {
+        duplicate_lyrics(&self.entries.lock().unwrap())
+    }
+
+    fn push(&self, file: &Path, outcome: Outcome) {
+        let entry = ManifestEntry { file: file.to_path_buf(), outcome };
+        self.results.record(&entry);
+        self.entries.lock().unwrap().push(entry);
+    }
+
+    /// Writes the manifest into `dir`, named after the run's start time, and returns its path.
+    pub fn save(&self, dir: &Path, folder: &Path, mode: OutputMode, cancelled: bool) -> io::Result<PathBuf> {
+        let mut files = self.entries.lock().unwrap().clone();
+        files.sort_by(|a, b| a.file.cmp(&b.file));
+        let manifest = RunManifest {
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            started_at: self.started_at.clone(),
+            finished_at: now(),
+            folder: folder.to_path_buf(),
+            mode,
+            cancelled,
+            files,
+        };
+        fs::create_dir_all(dir)?;
+        let path = dir.join(format!("run-{}.json", self.started_at.replace(':', "-")));
+        fs::write(&path, serde_json::to_string_pretty(&manifest)?)?;
+        Ok(path)
+    }
+}
+
+/// Groups the written files whose lyrics are byte-identical to another file's.
+///
+/// Different songs sharing the exact same lyrics is a common sign of a wrong match, for example
+/// on albums with similar track titles, so these groups are worth a second look.
+pub fn duplicate_lyrics(entries: &[ManifestEntry]) -> Vec<Vec<PathBuf>> {
+    let mut by_hash: BTreeMap<&str, Vec<PathBuf>> = BTreeMap::new();
+    for entry in entries {
+        if let Outcome::Written { lyrics_sha256, .. } = &entry.outcome {
+            by_hash.entry(lyrics_sha256).or_default().push(entry.file.clone());
+        }
+    }
+    // A file that got the same lyrics in its `.lrc` file and its tags is listed once
+    for files in by_hash.values_mut() {
+        files.sort();
+        files.dedup();
+    }
+    let mut groups: Vec<_> = by_hash.into_values().filter(|files| files.len() > 1).collect();
+    groups.sort();
+    groups
+}
+
+/// What changed for the files of a folder between two runs.
+#[derive(Default)]
+pub struct RunDelta {
+    /// Files that have lyrics now but didn't get any in the previous run.
+    pub covered: Vec<PathBuf>,
+    /// Files that got lyrics in the previous run but failed this time.
+    pub regressed: Vec<PathBuf>,
+    /// Files whose lyrics were written in both runs but differ.
+    pub changed: Vec<PathBuf>,
+}
+
+impl RunDelta {
+    pub fn is_empty(&self) -> bool {
+        self.covered.is_empty() && self.regressed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Compares the entries of a run with those of the `previous` run of the same folder.
+pub fn compare(previous: &[ManifestEntry], current: &[ManifestEntry]) -> RunDelta {
+    let previous: BTreeMap<&Path, &Outcome> = previous.iter().map(|entry| (entry.file.as_path(), &entry.outcome)).collect();
+    let mut delta = RunDelta::default();
+    for entry in current {
+        let before = previous.get(entry.file.as_path()).copied();
+        let had_lyrics = matches!(before, Some(Outcome::Written { .. } | Outcome::Kept { .. }));
+        match (&entry.outcome, before) {
+            (Outcome::Written { lyrics_sha256: new, .. }, Some(Outcome::Written { lyrics_sha256: old, .. })) if new != old => {
+                delta.changed.push(entry.file.clone())
+            }
+            (Outcome::Written { .. } | Outcome::Kept { .. }, _) if !had_lyrics => delta.covered.push(entry.file.clone()),
+            (Outcome::Failed { .. }, _) if had_lyrics => delta.regressed.push(entry.file.clone()),
+            _ => {}
+        }
+    }
+    for files in [&mut delta.covered, &mut delta.regressed, &mut delta.changed] {
+        files.sort();
+    }
+    delta
+}
+
+fn now() -> String {
+    chrono::Local::now().format("%Y-%m-%dT%H:%M:%S").to_string()
+}
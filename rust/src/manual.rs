@@ -0,0 +1,154 @@
+//! Manual lookup of a single file's lyrics, for tracks the automatic matching got wrong or found
+//! nothing for.
+//!
+//! The user edits the query, previews the lyrics of lrclib's search results and picks the one to
+//! save. The pick is then written like the result of a run.
+
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use eframe::egui;
+
+use crate::network::{self, http_client};
+use crate::progress::format_duration;
+use crate::{labelled, LyricsResult, OutputMode};
+
+enum SearchState {
+    Idle,
+    Searching,
+    Done(Vec<LyricsResult>),
+    Failed(String),
+}
+
+/// What the user decided in the dialog.
+pub enum Choice {
+    /// Write the lyrics of the picked result in `OutputMode::Lrc` or `OutputMode::Embed`.
+    Save(LyricsResult, OutputMode),
+    Close,
+}
+
+/// The manual search dialog of one file.
+pub struct ManualSearch {
+    pub file: PathBuf,
+    title: String,
+    artist: String,
+    state: Arc<Mutex<SearchState>>,
+    selected: Option<usize>,
+}
+
+impl ManualSearch {
+    /// Opens the dialog for `file`, prefilled with the title and artist read from it.
+    pub fn new(file: PathBuf, title: String, artist: String) -> Self {
+        Self { file, title, artist, state: Arc::new(Mutex::new(SearchState::Idle)), selected: None }
+    }
+
+    /// Searches lrclib for the query on a helper thread, repainting `ctx` once the answer is in.
+    fn search(&mut self, ctx: &egui::Context) {
+        self.selected = None;
+        *self.state.lock().unwrap() = SearchState::Searching;
+        let state = Arc::clone(&self.state);
+        let ctx = ctx.clone();
+        let query = format!(
+            "https://lrclib.net/api/search?track_name={}&artist_name={}",
+            urlencoding::encode(self.title.trim()),
+            urlencoding::encode(self.artist.trim())
+        );
+        thread::spawn(move || {
+            let search = async { http_client().get(&query).send().await?.error_for_status()?.json::<Vec<LyricsResult>>().await };
+            let result = network::runtime().block_on(search);
+            *state.lock().unwrap() = match result {
+                Ok(results) => SearchState::Done(results),
+                Err(e) => SearchState::Failed(e.to_string()),
+            };
+            ctx.request_repaint();
+        });
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<Choice> {
+        let mut choice = None;
+        let mut open = true;
+        egui::Window::new("Search lyrics manually").open(&mut open).collapsible(false).show(ctx, |ui| {
+            ui.label(self.file.display().to_string());
+            let submitted = ui
+                .horizontal(|ui| {
+                    let title = labelled(ui, "Title:", egui::TextEdit::singleline(&mut self.title).desired_width(180.0));
+                    let artist = labelled(ui, "Artist:", egui::TextEdit::singleline(&mut self.artist).desired_width(140.0));
+                    let entered = (title.lost_focus() || artist.lost_focus()) && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                    ui.button("Search").clicked() || entered
+                })
+                .inner;
+            if submitted && !self.title.trim().is_empty() {
+                self.search(ctx);
+            }
+
+            let state = self.state.lock().unwrap();
+            match &*state {
+                SearchState::Idle => {}
+                SearchState::Searching => {
+                    ui.spinner();
+                }
+                SearchState::Failed(e) => {
+                    ui.label(format!("❌ Search failed: {}", e));
+                }
+                SearchState::Done(results) if results.is_empty() => {
+                    ui.label("lrclib has no results for this search.");
+                }
+                SearchState::Done(results) => {
+                    egui::ScrollArea::vertical().id_source("manual_results").max_height(180.0).show(ui, |ui| {
+                        for (index, result) in results.iter().enumerate() {
+                            if ui.selectable_label(self.selected == Some(index), describe(result)).clicked() {
+                                self.selected = Some(index);
+                            }
+                        }
+                    });
+                    if let Some(result) = self.selected.and_then(|index| results.get(index)) {
+                        ui.separator();
+                        let lyrics = result.synced_lyrics.as_deref().or(result.plain_lyrics.as_deref());
+                        egui::ScrollArea::vertical().id_source("manual_preview").max_height(250.0).show(ui, |ui| {
+                            ui.label(lyrics.unwrap_or("This result has no lyrics."));
+                        });
+                        ui.add_enabled_ui(lyrics.is_some_and(|lyrics| !lyrics.trim().is_empty()), |ui| {
+                            ui.horizontal(|ui| {
+                                if ui.button("Save as .lrc").clicked() {
+                                    choice = Some(Choice::Save(result.clone(), OutputMode::Lrc));
+                                }
+                                if ui.button("Embed in tags").clicked() {
+                                    choice = Some(Choice::Save(result.clone(), OutputMode::Embed));
+                                }
+                            });
+                        });
+                    }
+                }
+            }
+        });
+        if !open {
+            choice = Some(Choice::Close);
+        }
+        choice
+    }
+}
+
+/// One line describing a search result: artist, title, album, length and kind of lyrics.
+fn describe(result: &LyricsResult) -> String {
+    let mut text = format!(
+        "{} – {}",
+        result.artist_name.as_deref().unwrap_or("?"),
+        result.track_name.as_deref().unwrap_or("?")
+    );
+    if let Some(album) = &result.album_name {
+        text.push_str(&format!(" · {}", album));
+    }
+    if let Some(duration) = result.duration.filter(|duration| duration.is_finite() && *duration > 0.0) {
+        text.push_str(&format!(" · {}", format_duration(Duration::from_secs_f64(duration))));
+    }
+    let kind = match (&result.synced_lyrics, &result.plain_lyrics) {
+        _ if result.instrumental => "instrumental",
+        (Some(_), _) => "synced",
+        (None, Some(_)) => "plain",
+        (None, None) => "no lyrics",
+    };
+    text.push_str(&format!(" · {}", kind));
+    text
+}
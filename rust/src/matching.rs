@@ -0,0 +1,232 @@
+//! Scoring search results against the track they are meant for.
+//!
+//! A search returns every record resembling the query, including karaoke versions, covers and
+//! other artists' songs of the same name, so taking the first one often gives the wrong lyrics.
+//! Each candidate is scored instead by how close its title and artist are to the track's, and its
+//! length to the track's length. The best one is only used if its score reaches the profile's
+//! `MatchRules::min_score`.
+
+use std::cmp::Reverse;
+
+use serde::{Deserialize, Serialize};
+use similar::TextDiff;
+
+use crate::providers::Query;
+use crate::LyricsResult;
+
+/// Lengths at most this far apart score full marks, as providers round them differently.
+const DURATION_TOLERANCE_SECS: f64 = 2.0;
+/// Lengths this far apart or further score nothing.
+const MAX_DURATION_DIFFERENCE_SECS: f64 = 15.0;
+/// Words marking a candidate as another version of the song, unless the track's title has them
+/// too. Such candidates get half the title score.
+const VERSION_MARKERS: [&str; 7] = ["karaoke", "instrumental", "cover", "live", "remix", "acoustic", "tribute"];
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MatchRules {
+    /// Score in percent the best search result needs to be used, 0 to always use it.
+    pub min_score: u8,
+}
+
+impl Default for MatchRules {
+    fn default() -> Self {
+        Self { min_score: 60 }
+    }
+}
+
+impl MatchRules {
+    /// Whether a candidate with `score`, from 0 to 1, is close enough to be used.
+    pub fn accepts(&self, score: f64) -> bool {
+        score * 100.0 >= f64::from(self.min_score)
+    }
+}
+
+/// The candidate that matches `query` best, with its score. Of equally good candidates, the first
+/// one from the track's album is picked, as songs with common titles often have several records.
+/// Queries without an artist, such as a blank or "-" artist tag, match nothing.
+pub fn best(candidates: Vec<LyricsResult>, query: &Query) -> Option<(LyricsResult, f64)> {
+    // With no artist to compare, a song by anyone with the same title scores 0.625, or 0.7 with
+    // the same length, which is above the default minimum of 60
+    if words(query.artist).is_empty() {
+        return None;
+    }
+    let album = query.album.map(words).filter(|album| !album.is_empty());
+    let from_album = |candidate: &LyricsResult| album.is_some() && candidate.album_name.as_deref().map(words) == album;
+    candidates
+        .into_iter()
+        .enumerate()
+        .map(|(index, candidate)| (score(&candidate, query), from_album(&candidate), Reverse(index), candidate))
+        .max_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)).then(a.2.cmp(&b.2)))
+        .map(|(score, _, _, candidate)| (candidate, score))
+}
+
+/// How well `candidate` matches `query`, from 0 to 1. The title counts for half of it, the artist
+/// for 30% and the length for 20%. If either length is unknown, title and artist share all of it.
+fn score(candidate: &LyricsResult, query: &Query) -> f64 {
+    let title = candidate.track_name.as_deref().map_or(0.0, |title| title_similarity(title, query.title));
+    let artist = candidate.artist_name.as_deref().map_or(0.0, |artist| artist_similarity(artist, query.artist));
+    let duration = match (candidate.duration, query.duration) {
+        (Some(candidate), Some(track)) => {
+            let difference = (candidate - track.as_secs_f64()).abs();
+            let excess = (difference - DURATION_TOLERANCE_SECS).max(0.0);
+            Some((1.0 - excess / (MAX_DURATION_DIFFERENCE_SECS - DURATION_TOLERANCE_SECS)).max(0.0))
+        }
+        _ => None,
+    };
+    match duration {
+        Some(duration) => 0.5 * title + 0.3 * artist + 0.2 * duration,
+        None => 0.625 * title + 0.375 * artist,
+    }
+}
+
+/// Similarity of two texts from 0 to 1, ignoring case and punctuation: the better of how little
+/// editing turns one into the other, and how many of their words they share.
+fn similarity(a: &str, b: &str) -> f64 {
+    let (a, b) = (words(a), words(b));
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    let (a_text, b_text) = (a.join(" "), b.join(" "));
+    let characters = f64::from(TextDiff::from_chars(a_text.as_str(), b_text.as_str()).ratio());
+    let shared = a.iter().filter(|word| b.contains(word)).count().min(b.len());
+    let words = 2.0 * shared as f64 / (a.len() + b.len()) as f64;
+    characters.max(words)
+}
+
+/// `similarity` of a candidate's title to the track's, halved if the candidate is another version
+/// of the song, see `VERSION_MARKERS`.
+fn title_similarity(candidate: &str, track: &str) -> f64 {
+    let (candidate_words, track_words) = (words(candidate), words(track));
+    let other_version = VERSION_MARKERS
+        .iter()
+        .any(|marker| candidate_words.iter().any(|word| word == marker) && !track_words.iter().any(|word| word == marker));
+    let similarity = similarity(candidate, track);
+    if other_version {
+        similarity / 2.0
+    } else {
+        similarity
+    }
+}
+
+/// `similarity` of two artists, where one naming all of the other's words, such as "Artist" and
+/// "Artist feat. Guest", counts as nearly the same.
+fn artist_similarity(candidate: &str, track: &str) -> f64 {
+    let (candidate_words, track_words) = (words(candidate), words(track));
+    let contains = |outer: &[String], inner: &[String]| !inner.is_empty() && inner.iter().all(|word| outer.contains(word));
+    let similarity = similarity(candidate, track);
+    if contains(&candidate_words, &track_words) || contains(&track_words, &candidate_words) {
+        similarity.max(0.9)
+    } else {
+        similarity
+    }
+}
+
+/// The lowercase words of `text`, without punctuation.
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric()).filter(|word| !word.is_empty()).map(str::to_lowercase).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn candidate(title: &str, artist: &str, album: Option<&str>, duration: Option<f64>) -> LyricsResult {
+        LyricsResult {
+            id: None,
+            track_name: Some(title.to_string()),
+            artist_name: Some(artist.to_string()),
+            album_name: album.map(str::to_string),
+            duration,
+            synced_lyrics: None,
+            plain_lyrics: None,
+            instrumental: false,
+        }
+    }
+
+    fn query<'a>(title: &'a str, artist: &'a str, duration: Option<u64>) -> Query<'a> {
+        Query { title, artist, album: None, duration: duration.map(Duration::from_secs) }
+    }
+
+    #[test]
+    fn picks_the_original_over_a_karaoke_version_listed_first() {
+        let candidates = vec![
+            candidate("Love (Karaoke Version)", "Singer", None, Some(200.0)),
+            candidate("Love", "Singer", None, Some(200.0)),
+        ];
+        let (best, score) = best(candidates, &query("Love", "Singer", Some(200))).unwrap();
+        assert_eq!(best.track_name.as_deref(), Some("Love"));
+        assert!((score - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn karaoke_version_alone_is_rejected_by_default() {
+        let candidates = vec![candidate("Love (Karaoke Version)", "Karaoke Band", None, None)];
+        let (_, score) = best(candidates, &query("Love", "Singer", None)).unwrap();
+        assert!(!MatchRules::default().accepts(score));
+    }
+
+    #[test]
+    fn version_marker_in_the_track_title_is_not_penalized() {
+        let track = query("Love (Live)", "Singer", None);
+        assert_eq!(score(&candidate("Love (Live)", "Singer", None, None), &track), 1.0);
+    }
+
+    #[test]
+    fn query_without_artist_matches_nothing() {
+        let candidates = vec![candidate("Love", "Singer", None, Some(200.0))];
+        assert!(best(candidates.clone(), &query("Love", "", Some(200))).is_none());
+        assert!(best(candidates, &query("Love", " - ", Some(200))).is_none());
+    }
+
+    #[test]
+    fn candidate_without_artist_scores_only_its_title() {
+        let track = query("Love", "Singer", None);
+        assert_eq!(score(&candidate("Love", "", None, None), &track), 0.625);
+    }
+
+    #[test]
+    fn featured_artists_count_as_nearly_the_same_artist() {
+        assert_eq!(artist_similarity("Singer feat. Guest", "Singer"), 0.9);
+        assert_eq!(artist_similarity("Singer", "Singer & Guest"), 0.9);
+        assert!(artist_similarity("Someone Else", "Singer") < 0.5);
+    }
+
+    #[test]
+    fn lengths_within_the_tolerance_score_full_marks() {
+        let track = query("Love", "Singer", Some(200));
+        let exact = score(&candidate("Love", "Singer", None, Some(200.0)), &track);
+        let close = score(&candidate("Love", "Singer", None, Some(198.0)), &track);
+        let far = score(&candidate("Love", "Singer", None, Some(230.0)), &track);
+        assert_eq!(exact, 1.0);
+        assert_eq!(close, 1.0);
+        assert!((far - 0.8).abs() < 1e-9);
+    }
+
+    #[test]
+    fn unknown_length_leaves_title_and_artist_all_of_the_score() {
+        let track = query("Love", "Singer", None);
+        assert_eq!(score(&candidate("Love", "Singer", None, Some(230.0)), &track), 1.0);
+    }
+
+    #[test]
+    fn ties_go_to_the_track_album() {
+        let candidates = vec![
+            candidate("Love", "Singer", Some("Greatest Hits"), None),
+            candidate("Love", "Singer", Some("Debut"), None),
+        ];
+        let track = Query { album: Some("debut"), ..query("Love", "Singer", None) };
+        let (best, _) = best(candidates, &track).unwrap();
+        assert_eq!(best.album_name.as_deref(), Some("Debut"));
+    }
+
+    #[test]
+    fn minimum_score_is_inclusive() {
+        let rules = MatchRules { min_score: 60 };
+        assert!(rules.accepts(0.6));
+        assert!(!rules.accepts(0.599));
+        assert!(MatchRules { min_score: 0 }.accepts(0.0));
+        assert!(!MatchRules { min_score: 100 }.accepts(0.99));
+    }
+}
@@ -0,0 +1,63 @@
+//! Lookups shared by the copies of a track within a run.
+//!
+//! Libraries often hold the same album in several places, such as a lossless rip and a copy
+//! converted for a player. Within a run, a track is looked up once per normalized artist, title,
+//! album and length, and every further copy gets the same answer, even if it is read while the
+//! first lookup is still waiting for the provider. The album is part of it as lrclib is asked for
+//! the recording on that album, the same as for the `LookupCache`. Unlike that cache, this covers
+//! the whole lookup, including the second try with a cleaned up title, and it works without the
+//! cache file.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::failure::Failure;
+use crate::provenance::Provenance;
+use crate::providers::Query;
+
+/// Copies of a track whose lengths differ by less than this usually share a bucket, as rips and
+/// encoders disagree by a second or so.
+const DURATION_BUCKET_SECS: u64 = 3;
+
+type Lookup = Result<(String, Provenance), Failure>;
+
+#[derive(Default)]
+pub struct RunLookups {
+    /// The lookup of each track, filled in by the first file that asked for it.
+    lookups: Mutex<HashMap<String, Arc<Mutex<Option<Lookup>>>>>,
+}
+
+impl RunLookups {
+    /// Returns the answer of an earlier lookup of `query` in this run, or looks it up with `fetch`.
+    /// The second value is true if the answer was reused.
+    ///
+    /// Only found lyrics and "no match" are shared. Timeouts and network errors are left to every
+    /// copy to try again, as they may have been bad luck.
+    pub fn get_or_fetch(&self, query: &Query, fetch: impl FnOnce() -> Lookup) -> (Lookup, bool) {
+        let slot = Arc::clone(self.lookups.lock().unwrap().entry(key(query)).or_default());
+        // Held during the lookup, so copies read at the same time wait for it instead of asking too
+        let mut slot = slot.lock().unwrap();
+        if let Some(lookup) = &*slot {
+            return (lookup.clone(), true);
+        }
+        let lookup = fetch();
+        if matches!(lookup, Ok(_) | Err(Failure::NoMatch)) {
+            *slot = Some(lookup.clone());
+        }
+        (lookup, false)
+    }
+}
+
+/// The track's artist, title and album, lowercased with runs of whitespace collapsed, and its
+/// length rounded into buckets of `DURATION_BUCKET_SECS`.
+fn key(query: &Query) -> String {
+    let normalize = |text: &str| text.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+    let album = query.album.map_or(String::new(), normalize);
+    let bucket = query.duration.map_or(String::new(), |duration| bucket(duration).to_string());
+    format!("{}\u{1f}{}\u{1f}{}\u{1f}{}", normalize(query.artist), normalize(query.title), album, bucket)
+}
+
+fn bucket(duration: Duration) -> u64 {
+    (duration.as_secs() + DURATION_BUCKET_SECS / 2) / DURATION_BUCKET_SECS
+}
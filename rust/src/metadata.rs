@@ -0,0 +1,85 @@
+//! Track metadata from outside the audio file's tags.
+//!
+//! Downloaded collections often have no usable tags, but the tool that fetched them left a
+//! description behind: Kodi-style `.nfo` files or the `.info.json` files written by yt-dlp.
+//! Where there is none, the user can type the title and artist in, see `MetadataFix`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use lofty::{Accessor, AudioFile, Tag, TaggedFileExt};
+use serde::Deserialize;
+
+/// A file skipped for missing metadata, with the title and artist typed in to retry it.
+pub struct MetadataFix {
+    pub file: PathBuf,
+    pub title: String,
+    pub artist: String,
+}
+
+/// Writes `title` and `artist` to the primary tag of `path`, creating the tag if needed.
+pub fn write_to_tags(path: &Path, title: &str, artist: &str) -> lofty::Result<()> {
+    let mut tagged_file = lofty::read_from_path(path)?;
+    let tag_type = tagged_file.primary_tag_type();
+    if tagged_file.tag(tag_type).is_none() {
+        tagged_file.insert_tag(Tag::new(tag_type));
+    }
+    if let Some(tag) = tagged_file.tag_mut(tag_type) {
+        tag.set_title(title.to_string());
+        tag.set_artist(artist.to_string());
+    }
+    tagged_file.save_to_path(path)
+}
+
+/// The fields of a yt-dlp `.info.json` file that name the track.
+#[derive(Deserialize)]
+struct InfoJson {
+    track: Option<String>,
+    artist: Option<String>,
+    creator: Option<String>,
+    title: Option<String>,
+}
+
+/// Reads title and artist from a `.info.json` or `.nfo` file next to `audio`.
+///
+/// Returns None if there is neither, or neither names a title.
+pub fn from_sidecar(audio: &Path) -> Option<(Option<String>, Option<String>)> {
+    let (title, artist) = from_info_json(audio).or_else(|| from_nfo(audio))?;
+    Some((Some(title?), artist))
+}
+
+fn from_info_json(audio: &Path) -> Option<(Option<String>, Option<String>)> {
+    let json = fs::read_to_string(audio.with_extension("info.json")).ok()?;
+    let info: InfoJson = serde_json::from_str(&json).ok()?;
+    let artist = info.artist.or(info.creator);
+    match (info.track, artist) {
+        (Some(track), artist) => Some((Some(track), artist)),
+        // Video titles of music uploads are usually "Artist - Title"
+        (None, None) => {
+            let title = info.title?;
+            match title.split_once(" - ") {
+                Some((artist, title)) => Some((Some(title.trim().to_string()), Some(artist.trim().to_string()))),
+                None => Some((Some(title), None)),
+            }
+        }
+        (None, artist) => Some((info.title, artist)),
+    }
+}
+
+fn from_nfo(audio: &Path) -> Option<(Option<String>, Option<String>)> {
+    let nfo = fs::read_to_string(audio.with_extension("nfo")).ok()?;
+    Some((element(&nfo, "title"), element(&nfo, "artist")))
+}
+
+/// The text of the first `<name>` element of an XML document, unescaped and trimmed.
+fn element(xml: &str, name: &str) -> Option<String> {
+    let start = xml.find(&format!("<{}>", name))? + name.len() + 2;
+    let end = start + xml[start..].find(&format!("</{}>", name))?;
+    let text = xml[start..end]
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&");
+    Some(text.trim().to_string()).filter(|text| !text.is_empty())
+}
@@ -0,0 +1,107 @@
+//! OpenMetrics endpoint of headless runs, so home-lab users can graph and alert on their library
+//! coverage with Prometheus or similar.
+//!
+//! `--metrics <address>` serves the counters of the run on every path of a plain HTTP server,
+//! such as `http://localhost:9464/metrics`. Counters start at zero when the app starts and keep
+//! growing while `--watch` picks up new files, as Prometheus expects.
+
+use std::fmt::Write as _;
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use crate::failure::Failure;
+use crate::{RunContext, CANCEL_POLL_INTERVAL};
+
+const CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+/// How long a scraper may take to send its request.
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Answers every connection to `listener` with the metrics of `run`, until `done` is set.
+pub fn serve(listener: &TcpListener, run: &RunContext, done: &AtomicBool) {
+    // Polled, so the server stops with the run instead of waiting for one more connection
+    if let Err(e) = listener.set_nonblocking(true) {
+        run.logs.lock().unwrap().push(format!("❌ Could not serve metrics: {}", e));
+        return;
+    }
+    while !done.load(Ordering::SeqCst) {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                if let Err(e) = respond(stream, run) {
+                    run.logs.lock().unwrap().push(format!("[DEBUG] Failed to answer a metrics request: {}", e));
+                }
+            }
+            Err(_) => thread::sleep(CANCEL_POLL_INTERVAL),
+        }
+    }
+}
+
+fn respond(mut stream: TcpStream, run: &RunContext) -> io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    // Every path and method gets the metrics, so the request itself doesn't matter
+    let mut request = [0; 4096];
+    let _ = stream.read(&mut request)?;
+    let body = render(run);
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        CONTENT_TYPE,
+        body.len(),
+        body
+    )
+}
+
+/// The metrics of `run` in the OpenMetrics text format.
+pub fn render(run: &RunContext) -> String {
+    let mut text = String::new();
+    let files = [
+        ("scanned", run.scanned.load(Ordering::SeqCst)),
+        ("written", run.written.load(Ordering::SeqCst)),
+        ("kept", run.kept.load(Ordering::SeqCst)),
+    ];
+    family(&mut text, "lyrics_downloader_files", "counter", "Audio files processed, by outcome.");
+    for (outcome, count) in files {
+        let _ = writeln!(text, "lyrics_downloader_files_total{{outcome=\"{}\"}} {}", outcome, count);
+    }
+
+    let failures = run.failures.counts();
+    family(&mut text, "lyrics_downloader_failures", "counter", "Files that got no lyrics, by reason.");
+    for failure in Failure::ALL {
+        let class = serde_json::to_value(failure).ok().and_then(|class| class.as_str().map(String::from)).unwrap_or_default();
+        let count = failures.get(&failure).copied().unwrap_or_default();
+        let _ = writeln!(text, "lyrics_downloader_failures_total{{class=\"{}\"}} {}", class, count);
+    }
+
+    let network = run.network.snapshot();
+    family(&mut text, "lyrics_downloader_api_requests", "counter", "Requests sent to the lyrics providers.");
+    let _ = writeln!(text, "lyrics_downloader_api_requests_total {}", network.requests);
+    family(&mut text, "lyrics_downloader_api_errors", "counter", "Provider requests that failed or were throttled.");
+    let _ = writeln!(text, "lyrics_downloader_api_errors_total {}", network.errors);
+    family(&mut text, "lyrics_downloader_api_requests_in_flight", "gauge", "Provider requests waiting for an answer.");
+    let _ = writeln!(text, "lyrics_downloader_api_requests_in_flight {}", network.in_flight);
+    family(&mut text, "lyrics_downloader_api_request_duration_seconds", "summary", "Time provider requests took to answer.");
+    let _ = writeln!(text, "# UNIT lyrics_downloader_api_request_duration_seconds seconds");
+    let _ = writeln!(text, "lyrics_downloader_api_request_duration_seconds_sum {}", network.latency.as_secs_f64());
+    let _ = writeln!(text, "lyrics_downloader_api_request_duration_seconds_count {}", network.finished);
+
+    if let Some(cache) = &run.lookup_cache {
+        let (hits, misses) = cache.hit_counts();
+        family(&mut text, "lyrics_downloader_cache_lookups", "counter", "Provider lookups, by whether the cache answered them.");
+        let _ = writeln!(text, "lyrics_downloader_cache_lookups_total{{result=\"hit\"}} {}", hits);
+        let _ = writeln!(text, "lyrics_downloader_cache_lookups_total{{result=\"miss\"}} {}", misses);
+        let ratio = if hits + misses == 0 { 0.0 } else { hits as f64 / (hits + misses) as f64 };
+        family(&mut text, "lyrics_downloader_cache_hit_ratio", "gauge", "Share of provider lookups the cache answered.");
+        let _ = writeln!(text, "lyrics_downloader_cache_hit_ratio {}", ratio);
+    }
+    text.push_str("# EOF\n");
+    text
+}
+
+fn family(text: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(text, "# TYPE {} {}", name, kind);
+    let _ = writeln!(text, "# HELP {} {}", name, help);
+}
@@ -0,0 +1,91 @@
+//! Publishes run events to an MQTT broker, so Home Assistant and similar systems can show
+//! notifications and trigger automations when a run starts or finishes.
+//!
+//! Every event is a JSON object on the configured topic, with an `event` field of `started` or
+//! `finished`. Finished events carry the run summary and a short human-readable `message`.
+
+use std::io;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use rumqttc::{Client, Event, MqttOptions, Packet, QoS};
+use serde::{Deserialize, Serialize};
+
+use crate::history::RunSummary;
+use crate::OutputMode;
+
+/// How long to wait for the broker to acknowledge an event.
+const PUBLISH_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct MqttConfig {
+    pub enabled: bool,
+    pub host: String,
+    pub port: u16,
+    pub topic: String,
+    /// Left empty for brokers that allow anonymous clients.
+    #[serde(default)]
+    pub username: String,
+    #[serde(default)]
+    pub password: String,
+}
+
+impl Default for MqttConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: "localhost".to_string(),
+            port: 1883,
+            topic: "lyrics_downloader/run".to_string(),
+            username: String::new(),
+            password: String::new(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum RunEvent<'a> {
+    Started {
+        folder: &'a Path,
+        mode: OutputMode,
+    },
+    Finished {
+        message: String,
+        #[serde(flatten)]
+        summary: &'a RunSummary,
+    },
+}
+
+impl<'a> RunEvent<'a> {
+    pub fn finished(summary: &'a RunSummary) -> Self {
+        let failed: usize = summary.failures.values().sum();
+        let verb = if summary.cancelled { "cancelled" } else { "completed" };
+        let message = format!("Lyrics sync {}, {} new, {} failed", verb, summary.written, failed);
+        RunEvent::Finished { message, summary }
+    }
+}
+
+/// Publishes `event` and waits until the broker has acknowledged it.
+pub fn publish(config: &MqttConfig, event: &RunEvent) -> io::Result<()> {
+    let payload = serde_json::to_vec(event)?;
+    let mut options = MqttOptions::new(format!("lyrics-downloader-{}", std::process::id()), &config.host, config.port);
+    options.set_keep_alive(Duration::from_secs(30));
+    if !config.username.is_empty() {
+        options.set_credentials(&config.username, &config.password);
+    }
+    let (client, mut connection) = Client::new(options, 10);
+    client.publish(&config.topic, QoS::AtLeastOnce, false, payload).map_err(io::Error::other)?;
+    let deadline = Instant::now() + PUBLISH_TIMEOUT;
+    loop {
+        match connection.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+            Ok(Ok(Event::Incoming(Packet::PubAck(_)))) => {
+                let _ = client.disconnect();
+                return Ok(());
+            }
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => return Err(io::Error::other(e)),
+            Err(_) => return Err(io::Error::new(io::ErrorKind::TimedOut, "the broker did not acknowledge the event")),
+        }
+    }
+}
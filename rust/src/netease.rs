@@ -0,0 +1,200 @@
+//! NetEase Cloud Music, which has synced lyrics for most Chinese, Japanese and Korean releases.
+//!
+//! The public web API needs no account. Songs are found through its search, and the lyrics of the
+//! best match are fetched by song id. NetEase's LRC differs from the usual format in a few ways,
+//! see `normalize_lrc`.
+
+use std::time::Duration;
+
+use reqwest::RequestBuilder;
+use reqwest::header::REFERER;
+use serde::Deserialize;
+
+use crate::failure::Failure;
+use crate::lrc;
+use crate::network::http_client;
+use crate::providers::{LyricsProvider, Query};
+use crate::{provider_request, settle, CancellationToken, LyricsResult, RunContext};
+
+const NETEASE: &str = "netease";
+
+/// Search results whose length differs from the track's by more than this aren't taken.
+const MAX_DURATION_DIFFERENCE: Duration = Duration::from_secs(3);
+
+pub struct NetEase;
+
+#[derive(Deserialize)]
+struct SearchResponse {
+    result: Option<SearchResult>,
+}
+
+#[derive(Deserialize)]
+struct SearchResult {
+    #[serde(default)]
+    songs: Vec<Song>,
+}
+
+#[derive(Deserialize)]
+struct Song {
+    id: u64,
+    name: String,
+    #[serde(default)]
+    artists: Vec<Artist>,
+    album: Option<Album>,
+    /// Length in milliseconds.
+    duration: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct Artist {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct Album {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct LyricResponse {
+    lrc: Option<Lyric>,
+    /// Set for instrumentals.
+    #[serde(default)]
+    nolyric: bool,
+}
+
+#[derive(Deserialize)]
+struct Lyric {
+    lyric: Option<String>,
+}
+
+/// A GET request to the NetEase web API, which turns away requests without its own site as referer.
+fn request(url: &str) -> RequestBuilder {
+    http_client().get(url).header(REFERER, "https://music.163.com/")
+}
+
+impl LyricsProvider for NetEase {
+    fn name(&self) -> &'static str {
+        NETEASE
+    }
+
+    /// Searches for "title artist" and takes the first song whose name starts with the query's
+    /// title, by the query's artist, with a length close to the track's if that is known.
+    fn fetch(&self, query: &Query, run: &RunContext, cancel: &CancellationToken) -> Result<Option<LyricsResult>, Failure> {
+        let search = request("https://music.163.com/api/search/get").query(&[
+            ("s", format!("{} {}", query.title, query.artist)),
+            ("type", "1".to_string()),
+            ("limit", "10".to_string()),
+        ]);
+        let parse = |body: &[u8]| serde_json::from_slice::<SearchResponse>(body).ok();
+        let Some(found) = settle(NETEASE, provider_request(NETEASE, search, parse, run, cancel)?, run)? else {
+            return Ok(None);
+        };
+        let songs = found.result.map(|result| result.songs).unwrap_or_default();
+        let Some(song) = songs.into_iter().find(|song| matches(song, query)) else {
+            return Ok(None);
+        };
+
+        let lyric = request("https://music.163.com/api/song/lyric").query(&[("id", song.id.to_string()), ("lv", "1".to_string())]);
+        let parse = |body: &[u8]| serde_json::from_slice::<LyricResponse>(body).ok();
+        let Some(lyric) = settle(NETEASE, provider_request(NETEASE, lyric, parse, run, cancel)?, run)? else {
+            return Ok(None);
+        };
+        let text = lyric.lrc.and_then(|lrc| lrc.lyric).filter(|text| !lyric.nolyric && !text.trim().is_empty());
+        let Some(text) = text else {
+            return Ok(lyric.nolyric.then(|| LyricsResult { instrumental: true, ..result(song, None, None) }));
+        };
+        let (synced_lyrics, plain_lyrics) = match normalize_lrc(&text) {
+            Some(synced) => (Some(synced), None),
+            None => (None, Some(text.trim().to_string())),
+        };
+        Ok(Some(result(song, synced_lyrics, plain_lyrics)))
+    }
+}
+
+fn result(song: Song, synced_lyrics: Option<String>, plain_lyrics: Option<String>) -> LyricsResult {
+    LyricsResult {
+        id: Some(song.id),
+        track_name: Some(song.name),
+        artist_name: Some(song.artists.into_iter().map(|artist| artist.name).collect::<Vec<_>>().join(", ")),
+        album_name: song.album.map(|album| album.name),
+        duration: song.duration.map(|millis| millis as f64 / 1000.0),
+        synced_lyrics,
+        plain_lyrics,
+        instrumental: false,
+    }
+}
+
+fn matches(song: &Song, query: &Query) -> bool {
+    let normalize = |text: &str| text.trim().to_lowercase();
+    let (title, artist) = (normalize(query.title), normalize(query.artist));
+    let duration_matches = match (song.duration, query.duration) {
+        (Some(millis), Some(duration)) => Duration::from_millis(millis).abs_diff(duration) <= MAX_DURATION_DIFFERENCE,
+        _ => true,
+    };
+    normalize(&song.name).starts_with(&title)
+        && song.artists.iter().any(|candidate| artist.contains(&normalize(&candidate.name)))
+        && duration_matches
+}
+
+/// Turns NetEase's LRC into the usual format. Returns None if it has no timed lines.
+///
+/// NetEase puts JSON-encoded credits in front of the lyrics, sometimes separates centiseconds
+/// with a colon (`[01:02:34]`), and opens with timed credit lines such as `作词 : ...`. Those are
+/// dropped or fixed, along with the metadata tags.
+fn normalize_lrc(text: &str) -> Option<String> {
+    let fixed: Vec<String> = text
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('{'))
+        .map(|line| {
+            let mut line = line.trim_start().to_string();
+            // "[01:02:34]" → "[01:02.34]"
+            if let Some(stamp) = line.strip_prefix('[').and_then(|rest| rest.split(']').next()) {
+                if let [minutes, seconds, centis] = stamp.split(':').collect::<Vec<_>>()[..] {
+                    let fixed = format!("{}:{}.{}", minutes, seconds, centis);
+                    line = line.replacen(stamp, &fixed, 1);
+                }
+            }
+            line
+        })
+        .collect();
+    let mut lines = lrc::timed_lines(&fixed.join("\n"));
+    let credits = lines.iter().take_while(|(_, text)| is_credit(text)).count();
+    lines.drain(..credits);
+    (!lines.is_empty()).then(|| lrc::format_timed_lines(&lines))
+}
+
+/// Whether a lyric line is a credit like `作词 : 方文山`, with a spaced or full-width colon.
+fn is_credit(text: &str) -> bool {
+    text.split_once(" : ").or_else(|| text.split_once('：')).is_some_and(|(role, name)| {
+        let role = role.trim();
+        !role.is_empty() && role.chars().count() <= 12 && !name.trim().is_empty()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn credits_and_metadata_are_dropped_and_colon_centiseconds_fixed() {
+        let text = "{\"t\":0,\"c\":[{\"tx\":\"credits\"}]}\n[ti:Song]\n[00:00.00]作词 : 方文山\n[00:01:50]作曲：周杰伦\n[00:12:34]first line\n[00:15.00]second line";
+        assert_eq!(normalize_lrc(text).as_deref(), Some("[00:12.34]first line\n[00:15.00]second line\n"));
+    }
+
+    #[test]
+    fn songs_match_by_title_start_artist_and_length() {
+        let song: Song = serde_json::from_str(r#"{"id":1,"name":"晴天 (Live)","artists":[{"name":"周杰伦"}],"duration":269000}"#).unwrap();
+        let query = |title, artist, seconds: Option<u64>| Query { title, artist, album: None, duration: seconds.map(Duration::from_secs) };
+        assert!(matches(&song, &query("晴天", "周杰伦", Some(270))));
+        assert!(matches(&song, &query("晴天", "周杰伦 & 五月天", None)));
+        assert!(!matches(&song, &query("晴天", "五月天", Some(269))));
+        assert!(!matches(&song, &query("晴天", "周杰伦", Some(275))));
+    }
+
+    #[test]
+    fn lyrics_without_timed_lines_are_rejected() {
+        assert_eq!(normalize_lrc("[ti:Song]\nplain line"), None);
+        assert_eq!(normalize_lrc("[00:00.00]作词 : 方文山"), None);
+    }
+}
@@ -0,0 +1,261 @@
+//! Health statistics of the network requests made during a run, the adaptive limit on how
+//! many of them may run at once, and the HTTP client and runtime they are sent with.
+//!
+//! Requests are async and run on a small shared tokio runtime. The pipeline's worker threads
+//! wait for them with `block_on`, so a cancelled request is dropped on the spot instead of
+//! tying up a thread until it finishes. All providers share one client, built from the proxy,
+//! certificate and timeout settings of `HttpConfig`.
+
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Condvar, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use reqwest::{Certificate, Client, Proxy};
+use serde::{Deserialize, Serialize};
+use tokio::runtime::Runtime;
+
+use crate::{CancellationToken, CANCEL_POLL_INTERVAL};
+
+/// Identifies the app and its version to the providers, as lrclib asks its clients to.
+const USER_AGENT: &str = concat!("LyricsDownloader/", env!("CARGO_PKG_VERSION"), " (https://github.com/caberfan/LyricsDownloader)");
+
+/// How requests reach the providers, for networks that only let them out through a proxy or
+/// inspect TLS traffic with a certificate of their own.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct HttpConfig {
+    /// URL of the proxy all requests go through, `http://`, `https://` or `socks5://`, empty for
+    /// none. Credentials can be part of the URL.
+    #[serde(default)]
+    pub proxy: String,
+    /// PEM file with further certificate authorities to trust, e.g. a company's TLS inspection.
+    #[serde(default)]
+    pub ca_bundle: Option<PathBuf>,
+    /// Seconds a request may take before it counts as timed out.
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_timeout_secs() -> u64 {
+    30
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self { proxy: String::new(), ca_bundle: None, timeout_secs: default_timeout_secs() }
+    }
+}
+
+static CLIENT: Mutex<Option<Client>> = Mutex::new(None);
+
+/// The client all requests to lyrics providers are sent with. It sends `USER_AGENT`, keeps
+/// connections open between requests, and follows the settings last passed to `configure`.
+pub fn http_client() -> Client {
+    // Clients share their connection pool, so handing out copies is cheap
+    CLIENT.lock().unwrap().get_or_insert_with(|| build_client(&HttpConfig::default()).unwrap_or_else(|_| Client::new())).clone()
+}
+
+/// Builds the client from `config` for all further requests. Requests already sent finish with
+/// the previous one. If `config` is invalid, the previous client stays in use and the reason is
+/// returned.
+pub fn configure(config: &HttpConfig) -> Result<(), String> {
+    let client = build_client(config)?;
+    *CLIENT.lock().unwrap() = Some(client);
+    Ok(())
+}
+
+fn build_client(config: &HttpConfig) -> Result<Client, String> {
+    let mut builder = Client::builder().user_agent(USER_AGENT).timeout(Duration::from_secs(config.timeout_secs.max(1)));
+    let proxy = config.proxy.trim();
+    if !proxy.is_empty() {
+        builder = builder.proxy(Proxy::all(proxy).map_err(|e| format!("invalid proxy URL {}: {}", proxy, e))?);
+    }
+    if let Some(path) = &config.ca_bundle {
+        let pem = fs::read(path).map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+        let certificates = Certificate::from_pem_bundle(&pem).map_err(|e| format!("{} holds no valid certificates: {}", path.display(), e))?;
+        if certificates.is_empty() {
+            return Err(format!("{} holds no certificates", path.display()));
+        }
+        for certificate in certificates {
+            builder = builder.add_root_certificate(certificate);
+        }
+    }
+    builder.build().map_err(|e| e.to_string())
+}
+
+/// The runtime all requests to lyrics providers run on. Two threads are plenty, as they only
+/// drive sockets; how many requests are in flight is up to `AdaptiveConcurrency`.
+pub fn runtime() -> &'static Runtime {
+    static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+    RUNTIME.get_or_init(|| {
+        tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .thread_name("network")
+            .enable_all()
+            .build()
+            .expect("failed to start the network runtime")
+    })
+}
+
+/// Counters describing the requests of the current run, shown in the status area.
+#[derive(Default)]
+pub struct NetworkStats {
+    in_flight: AtomicUsize,
+    requests: AtomicUsize,
+    errors: AtomicUsize,
+    /// Requests that finished, and the time they took in total, in microseconds.
+    finished: AtomicUsize,
+    latency_micros: AtomicU64,
+}
+
+/// A point-in-time copy of `NetworkStats`.
+pub struct NetworkSnapshot {
+    pub in_flight: usize,
+    pub requests: usize,
+    pub errors: usize,
+    pub finished: usize,
+    /// Time the finished requests took in total.
+    pub latency: Duration,
+}
+
+impl NetworkSnapshot {
+    /// Share of finished requests that failed, between 0 and 1.
+    pub fn error_rate(&self) -> f32 {
+        let finished = self.requests.saturating_sub(self.in_flight);
+        if finished == 0 {
+            0.0
+        } else {
+            self.errors as f32 / finished as f32
+        }
+    }
+}
+
+impl NetworkStats {
+    /// Records the start of a request; it counts as in flight until the guard is dropped.
+    pub fn start_request(&self) -> InFlightGuard<'_> {
+        self.requests.fetch_add(1, Ordering::SeqCst);
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        InFlightGuard { stats: self, started: Instant::now() }
+    }
+
+    pub fn record_error(&self) {
+        self.errors.fetch_add(1, Ordering::SeqCst);
+    }
+
+    pub fn reset(&self) {
+        self.requests.store(0, Ordering::SeqCst);
+        self.errors.store(0, Ordering::SeqCst);
+        self.finished.store(0, Ordering::SeqCst);
+        self.latency_micros.store(0, Ordering::SeqCst);
+    }
+
+    pub fn snapshot(&self) -> NetworkSnapshot {
+        NetworkSnapshot {
+            in_flight: self.in_flight.load(Ordering::SeqCst),
+            requests: self.requests.load(Ordering::SeqCst),
+            errors: self.errors.load(Ordering::SeqCst),
+            finished: self.finished.load(Ordering::SeqCst),
+            latency: Duration::from_micros(self.latency_micros.load(Ordering::SeqCst)),
+        }
+    }
+}
+
+/// Marks a request as in flight for as long as it is alive, and records how long it took when
+/// dropped.
+pub struct InFlightGuard<'a> {
+    stats: &'a NetworkStats,
+    started: Instant,
+}
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        self.stats.in_flight.fetch_sub(1, Ordering::SeqCst);
+        self.stats.finished.fetch_add(1, Ordering::SeqCst);
+        self.stats.latency_micros.fetch_add(self.started.elapsed().as_micros() as u64, Ordering::SeqCst);
+    }
+}
+
+/// Limits the number of concurrent requests and adapts the limit to how the API copes.
+///
+/// The limit is halved whenever a request times out or is throttled, and raised by one after a
+/// full limit's worth of consecutive successes, up to the configured maximum.
+pub struct AdaptiveConcurrency {
+    state: Mutex<ConcurrencyState>,
+    released: Condvar,
+}
+
+struct ConcurrencyState {
+    limit: usize,
+    max: usize,
+    active: usize,
+    successes: usize,
+}
+
+impl AdaptiveConcurrency {
+    pub fn new(max: usize) -> Self {
+        let max = max.max(1);
+        Self {
+            state: Mutex::new(ConcurrencyState { limit: max, max, active: 0, successes: 0 }),
+            released: Condvar::new(),
+        }
+    }
+
+    /// Resets the limit to `max` at the start of a run.
+    pub fn reset(&self, max: usize) {
+        let mut state = self.state.lock().unwrap();
+        state.max = max.max(1);
+        state.limit = state.max;
+        state.successes = 0;
+    }
+
+    /// Waits until another request may start.
+    ///
+    /// Returns None if `cancel` fired while waiting.
+    pub fn acquire(&self, cancel: &CancellationToken) -> Option<ConcurrencyPermit<'_>> {
+        let mut state = self.state.lock().unwrap();
+        while state.active >= state.limit {
+            if cancel.is_cancelled() {
+                return None;
+            }
+            state = self.released.wait_timeout(state, CANCEL_POLL_INTERVAL).unwrap().0;
+        }
+        state.active += 1;
+        Some(ConcurrencyPermit { concurrency: self })
+    }
+
+    pub fn record_success(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.successes += 1;
+        if state.successes >= state.limit && state.limit < state.max {
+            state.limit += 1;
+            state.successes = 0;
+            self.released.notify_one();
+        }
+    }
+
+    /// Records a timeout or throttled request and halves the limit.
+    pub fn record_overload(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.limit = (state.limit / 2).max(1);
+        state.successes = 0;
+    }
+
+    /// Returns the current and the maximum limit.
+    pub fn limits(&self) -> (usize, usize) {
+        let state = self.state.lock().unwrap();
+        (state.limit, state.max)
+    }
+}
+
+/// Allows one request to run; the slot is handed to the next waiter when dropped.
+pub struct ConcurrencyPermit<'a> {
+    concurrency: &'a AdaptiveConcurrency,
+}
+
+impl Drop for ConcurrencyPermit<'_> {
+    fn drop(&mut self) {
+        self.concurrency.state.lock().unwrap().active -= 1;
+        self.concurrency.released.notify_one();
+    }
+}
@@ -0,0 +1,165 @@
+//! Cleaning up titles and artists before they are looked up again.
+//!
+//! Tags often carry release details that the providers leave out of their titles, such as
+//! "(Remastered 2011)", "[Explicit]", "- Live at Wembley" or "feat. X". When no provider has
+//! lyrics for a track as tagged, the lookup is retried with those parts removed, see `normalize`.
+
+use serde::{Deserialize, Serialize};
+
+/// Which parts of a title and artist `normalize` removes. Kept per profile in the config file.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueryRules {
+    /// Retry lookups that found nothing with the cleaned up title and artist.
+    pub enabled: bool,
+    /// Remove any `(...)`, `[...]` or `{...}` group at the end of a title.
+    pub strip_brackets: bool,
+    /// Remove featured artists ("feat.", "ft.", "featuring") from titles and artists.
+    pub strip_featuring: bool,
+    /// Comma-separated words marking a bracketed group or " - " suffix at the end of a title as
+    /// a release detail to remove. They match the start of a word, ignoring case, so "remaster"
+    /// also matches "Remastered".
+    pub qualifiers: String,
+}
+
+impl Default for QueryRules {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            strip_brackets: true,
+            strip_featuring: true,
+            qualifiers: "remaster, deluxe, live, explicit, clean, radio edit, single version, album version, mono, stereo, bonus"
+                .to_string(),
+        }
+    }
+}
+
+impl QueryRules {
+    fn qualifiers(&self) -> impl Iterator<Item = String> + '_ {
+        self.qualifiers.split(',').map(|qualifier| qualifier.trim().to_lowercase()).filter(|qualifier| !qualifier.is_empty())
+    }
+
+    /// Whether `text`, the inside of a bracketed group or a " - " suffix, names a release detail.
+    fn is_qualifier(&self, text: &str) -> bool {
+        let words: Vec<String> = text
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(str::to_lowercase)
+            .collect();
+        let words = format!(" {}", words.join(" "));
+        self.qualifiers().any(|qualifier| words.contains(&format!(" {}", qualifier)))
+    }
+}
+
+/// The title and artist with the parts `rules` remove, or None if the rules are off or would
+/// change nothing, in which case a second lookup is pointless.
+pub fn normalize(title: &str, artist: &str, rules: &QueryRules) -> Option<(String, String)> {
+    if !rules.enabled {
+        return None;
+    }
+    let mut new_title = title.trim().to_string();
+    let mut new_artist = artist.trim().to_string();
+    if rules.strip_featuring {
+        new_title = without_featuring(&new_title);
+        new_artist = without_featuring(&new_artist);
+    }
+    // Suffixes can be stacked, as in "Song - Live (Remastered 2011) [Explicit]"
+    loop {
+        let before = new_title.len();
+        if let Some((rest, inside)) = split_bracket_suffix(&new_title) {
+            if rules.strip_brackets || rules.is_qualifier(inside) {
+                new_title = rest.to_string();
+            }
+        }
+        if let Some((rest, suffix)) = new_title.rsplit_once(" - ") {
+            if rules.is_qualifier(suffix) && !rest.trim().is_empty() {
+                new_title = rest.trim_end().to_string();
+            }
+        }
+        if new_title.len() == before {
+            break;
+        }
+    }
+    let changed = new_title != title.trim() || new_artist != artist.trim();
+    (changed && !new_title.is_empty() && !new_artist.is_empty()).then_some((new_title, new_artist))
+}
+
+/// Splits a title ending in a bracketed group into the text before it and the group's inside.
+/// Returns None if the title doesn't end in one or nothing would be left of it.
+fn split_bracket_suffix(title: &str) -> Option<(&str, &str)> {
+    let open = match title.chars().last()? {
+        ')' => '(',
+        ']' => '[',
+        '}' => '{',
+        _ => return None,
+    };
+    let start = title.rfind(open)?;
+    let rest = title[..start].trim_end();
+    (!rest.is_empty()).then(|| (rest, &title[start + 1..title.len() - 1]))
+}
+
+/// `text` cut before its first featuring credit, along with the bracket the credit opens, if any.
+fn without_featuring(text: &str) -> String {
+    const CREDITS: [&str; 5] = ["feat. ", "feat ", "ft. ", "ft ", "featuring "];
+    let starts_credit = |index: usize| {
+        let rest = &text.as_bytes()[index..];
+        CREDITS.iter().any(|credit| rest.len() > credit.len() && rest[..credit.len()].eq_ignore_ascii_case(credit.as_bytes()))
+    };
+    let credit = text.char_indices().skip(1).find(|&(index, _)| {
+        let before = text[..index].chars().last();
+        before.is_some_and(|c| c.is_whitespace() || c == '(' || c == '[') && starts_credit(index)
+    });
+    let Some((index, _)) = credit else {
+        return text.to_string();
+    };
+    let rest = text[..index].trim_end_matches(|c: char| c.is_whitespace() || c == '(' || c == '[').trim_end();
+    if rest.is_empty() {
+        text.to_string()
+    } else {
+        rest.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalized(title: &str, artist: &str) -> Option<(String, String)> {
+        normalize(title, artist, &QueryRules::default())
+    }
+
+    #[test]
+    fn stacked_release_details_are_removed() {
+        let expected = Some(("Song".to_string(), "Artist".to_string()));
+        assert_eq!(normalized("Song - Live (Remastered 2011) [Explicit]", "Artist"), expected);
+    }
+
+    #[test]
+    fn featured_artists_are_removed_from_title_and_artist() {
+        let expected = Some(("Song".to_string(), "Artist".to_string()));
+        assert_eq!(normalized("Song (feat. Guest)", "Artist ft. Guest"), expected);
+    }
+
+    #[test]
+    fn dash_suffix_that_isnt_a_release_detail_is_kept() {
+        assert_eq!(normalized("Song - Part Two", "Artist"), None);
+    }
+
+    #[test]
+    fn nothing_to_remove_gives_no_second_lookup() {
+        assert_eq!(normalized("Song", "Artist"), None);
+        assert_eq!(normalize("Song (Live)", "Artist", &QueryRules { enabled: false, ..QueryRules::default() }), None);
+    }
+
+    #[test]
+    fn only_qualified_groups_are_removed_when_brackets_are_kept() {
+        let rules = QueryRules { strip_brackets: false, qualifiers: "acoustic".to_string(), ..QueryRules::default() };
+        let expected = Some(("Song (Interlude)".to_string(), "Artist".to_string()));
+        assert_eq!(normalize("Song (Interlude) [Acoustic]", "Artist", &rules), expected);
+        assert_eq!(normalize("Song (Remastered 2011)", "Artist", &rules), None);
+    }
+
+    #[test]
+    fn title_made_only_of_a_bracketed_group_is_kept() {
+        assert_eq!(normalized("(Intro)", "Artist"), None);
+    }
+}
@@ -0,0 +1,52 @@
+//! Desktop notifications when a run ends, so a long run doesn't finish unnoticed while the
+//! window is out of the way.
+//!
+//! A run queuing several folders sends a single notification with the totals of all of them.
+
+use notify_rust::Notification;
+use serde::{Deserialize, Serialize};
+
+use crate::failure;
+use crate::history::RunSummary;
+use crate::queue;
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct NotificationConfig {
+    /// Notify when a run finishes, with how many files got lyrics.
+    pub on_finish: bool,
+    /// Notify when files failed with an error, such as a network or write error, even if
+    /// `on_finish` is off. Tracks that simply have no lyrics don't count.
+    pub on_errors: bool,
+}
+
+impl Default for NotificationConfig {
+    fn default() -> Self {
+        Self { on_finish: true, on_errors: false }
+    }
+}
+
+/// Shows a notification about the run that produced `summaries`, if `config` asks for one.
+pub fn run_finished(config: NotificationConfig, summaries: &[RunSummary]) -> Result<(), notify_rust::error::Error> {
+    let scanned: usize = summaries.iter().map(|summary| summary.scanned).sum();
+    let written: usize = summaries.iter().map(|summary| summary.written).sum();
+    let mut errors = queue::total_failures(summaries);
+    errors.retain(|failure, _| failure.is_error());
+    let cancelled = summaries.iter().any(|summary| summary.cancelled);
+
+    let mut body = format!("Lyrics for {} of {} files.", written, scanned);
+    let summary = match failure::describe_counts(&errors) {
+        Some(errors) if config.on_errors => {
+            body += &format!("\nErrors: {}.", errors);
+            match cancelled {
+                true => "Lyrics sync cancelled with errors",
+                false => "Lyrics sync finished with errors",
+            }
+        }
+        _ if config.on_finish => match cancelled {
+            true => "Lyrics sync cancelled",
+            false => "Lyrics sync completed",
+        },
+        _ => return Ok(()),
+    };
+    Notification::new().appname("Lyrics Downloader").summary(summary).body(&body).show().map(|_| ())
+}
@@ -0,0 +1,165 @@
+//! Command palette, opened with Ctrl+P, listing the app's actions with fuzzy search.
+
+use eframe::egui;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Command {
+    SelectFolder,
+    WriteLrc,
+    Embed,
+    WriteLrcAndEmbed,
+    Preview,
+    ScanOnly,
+    ImportLrc,
+    ExtractLrc,
+    ConvertToSylt,
+    ConvertToUslt,
+    Cancel,
+    Pause,
+    Resume,
+    UndoLastRun,
+    ShowFailures,
+    ShowDuplicates,
+    OpenSettings,
+    OpenHistory,
+    OpenSearch,
+    ToggleDarkTheme,
+}
+
+impl Command {
+    pub const ALL: [Command; 20] = [
+        Command::SelectFolder,
+        Command::WriteLrc,
+        Command::Embed,
+        Command::WriteLrcAndEmbed,
+        Command::Preview,
+        Command::ScanOnly,
+        Command::ImportLrc,
+        Command::ExtractLrc,
+        Command::ConvertToSylt,
+        Command::ConvertToUslt,
+        Command::Cancel,
+        Command::Pause,
+        Command::Resume,
+        Command::UndoLastRun,
+        Command::ShowFailures,
+        Command::ShowDuplicates,
+        Command::OpenSettings,
+        Command::OpenHistory,
+        Command::OpenSearch,
+        Command::ToggleDarkTheme,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Command::SelectFolder => "Select folder",
+            Command::WriteLrc => "Start .lrc run",
+            Command::Embed => "Start embed run",
+            Command::WriteLrcAndEmbed => "Start .lrc + embed run",
+            Command::Preview => "Preview run (dry run)",
+            Command::ScanOnly => "Scan library statistics only",
+            Command::ImportLrc => "Import .lrc files into tags",
+            Command::ExtractLrc => "Extract embedded lyrics to .lrc files",
+            Command::ConvertToSylt => "Convert embedded lyrics to SYLT",
+            Command::ConvertToUslt => "Convert embedded lyrics to USLT",
+            Command::Cancel => "Cancel run",
+            Command::Pause => "Pause or resume run",
+            Command::Resume => "Resume interrupted run",
+            Command::UndoLastRun => "Undo last run",
+            Command::ShowFailures => "Show failures of the last run",
+            Command::ShowDuplicates => "Show duplicate lyrics of the last run",
+            Command::OpenSettings => "Open settings",
+            Command::OpenHistory => "Open history",
+            Command::OpenSearch => "Search lyrics",
+            Command::ToggleDarkTheme => "Toggle dark theme",
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Palette {
+    open: bool,
+    query: String,
+    selected: usize,
+}
+
+impl Palette {
+    /// Toggles the palette on Ctrl+P and shows it while it is open, offering `commands`.
+    ///
+    /// Arrow keys move the selection, Enter runs it and Esc closes the palette. Returns the
+    /// command that was picked, if any.
+    pub fn show(&mut self, ctx: &egui::Context, commands: &[Command]) -> Option<Command> {
+        if ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, egui::Key::P)) {
+            self.open = !self.open;
+            self.query.clear();
+            self.selected = 0;
+        }
+        if !self.open {
+            return None;
+        }
+        let mut matches: Vec<(usize, Command)> = commands
+            .iter()
+            .filter_map(|&command| Some((fuzzy_score(&self.query, command.label())?, command)))
+            .collect();
+        matches.sort_by_key(|&(score, _)| std::cmp::Reverse(score));
+        let (up, down, enter, escape) = ctx.input(|i| {
+            (
+                i.key_pressed(egui::Key::ArrowUp),
+                i.key_pressed(egui::Key::ArrowDown),
+                i.key_pressed(egui::Key::Enter),
+                i.key_pressed(egui::Key::Escape),
+            )
+        });
+        if down {
+            self.selected += 1;
+        }
+        if up {
+            self.selected = self.selected.saturating_sub(1);
+        }
+        self.selected = self.selected.min(matches.len().saturating_sub(1));
+
+        let mut picked = (enter && !matches.is_empty()).then(|| matches[self.selected].1);
+        egui::Window::new("Commands")
+            .collapsible(false)
+            .title_bar(false)
+            .anchor(egui::Align2::CENTER_TOP, [0.0, 40.0])
+            .show(ctx, |ui| {
+                let search = ui.add(egui::TextEdit::singleline(&mut self.query).hint_text("Type a command…"));
+                search.request_focus();
+                for (index, (_, command)) in matches.iter().enumerate() {
+                    if ui.selectable_label(index == self.selected, command.label()).clicked() {
+                        picked = Some(*command);
+                    }
+                }
+                if matches.is_empty() {
+                    ui.weak("No matching commands");
+                }
+            });
+        if picked.is_some() || escape {
+            self.open = false;
+        }
+        picked
+    }
+}
+
+/// Scores how well `query` matches `text`: every character of the query has to appear in the
+/// text in order, ignoring case. Consecutive characters and matches at word starts score higher.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<usize> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous = None;
+    for wanted in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = position + text[position..].iter().position(|&c| c == wanted)?;
+        score += 1;
+        if previous.is_some_and(|previous| previous + 1 == found) {
+            score += 2;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+        previous = Some(found);
+        position = found + 1;
+    }
+    Some(score)
+}
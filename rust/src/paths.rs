@@ -0,0 +1,150 @@
+//! Per-user locations of the app's files.
+//!
+//! Follows each platform's conventions via the `directories` crate: XDG directories on Linux,
+//! `AppData` on Windows and `Application Support`/`Caches` on macOS.
+//!
+//! Settings, history, manifests and caches belong to a profile, so one install can manage several
+//! libraries separately. The default profile lives directly in these directories, every other one
+//! in a `profiles/<name>` folder inside each of them. Logs are shared by all profiles.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock, RwLock};
+
+use directories::ProjectDirs;
+
+/// Name of the profile whose files live directly in the app's directories.
+pub const DEFAULT_PROFILE: &str = "Default";
+const PROFILES_DIR: &str = "profiles";
+/// File in the top-level config directory naming the profile to open on start.
+const ACTIVE_PROFILE_FILE: &str = "profile.txt";
+
+pub struct AppPaths {
+    pub profile: String,
+    /// Settings.
+    pub config_dir: PathBuf,
+    /// Databases, run manifests, the job journal and the undo log.
+    pub data_dir: PathBuf,
+    /// Data that can be rebuilt at any time.
+    pub cache_dir: PathBuf,
+    /// Log files.
+    pub log_dir: PathBuf,
+}
+
+impl AppPaths {
+    fn new(profile: &str) -> Option<Self> {
+        let dirs = project_dirs()?;
+        let scoped = |dir: &Path| match profile {
+            DEFAULT_PROFILE => dir.to_path_buf(),
+            _ => dir.join(PROFILES_DIR).join(profile),
+        };
+        Some(Self {
+            profile: profile.to_string(),
+            config_dir: scoped(dirs.config_dir()),
+            data_dir: scoped(dirs.data_dir()),
+            cache_dir: scoped(dirs.cache_dir()),
+            log_dir: dirs.data_local_dir().join("logs"),
+        })
+    }
+
+    /// Settings that persist between sessions.
+    pub fn config(&self) -> PathBuf {
+        self.config_dir.join("config.json")
+    }
+
+    /// Journal used to resume interrupted runs.
+    pub fn journal(&self) -> PathBuf {
+        self.data_dir.join("jobs.jsonl")
+    }
+
+    /// Changes made by the last run, to undo it.
+    pub fn undo_log(&self) -> PathBuf {
+        self.data_dir.join("undo.jsonl")
+    }
+
+    /// Accept and reject decisions made in review mode.
+    pub fn review_decisions(&self) -> PathBuf {
+        self.data_dir.join("review-decisions.json")
+    }
+
+    /// Summaries of past runs.
+    pub fn history(&self) -> PathBuf {
+        self.data_dir.join("history.jsonl")
+    }
+
+    /// Full-text index of the lyrics written so far.
+    pub fn search_index(&self) -> PathBuf {
+        self.cache_dir.join("search")
+    }
+
+    /// Answers of earlier lrclib lookups.
+    pub fn lookup_cache(&self) -> PathBuf {
+        self.cache_dir.join("lookups.json")
+    }
+
+    /// Folder holding the manifests of past runs.
+    pub fn manifests_dir(&self) -> PathBuf {
+        self.data_dir.join("manifests")
+    }
+}
+
+/// Returns the paths of the active profile, or None if the platform has no home directory to
+/// put them in.
+pub fn app_paths() -> Option<Arc<AppPaths>> {
+    active().read().unwrap().clone()
+}
+
+/// Names of the existing profiles, the default one first.
+pub fn profiles() -> Vec<String> {
+    let mut others: Vec<String> = project_dirs()
+        .and_then(|dirs| fs::read_dir(dirs.config_dir().join(PROFILES_DIR)).ok())
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| is_valid_profile_name(name))
+        .collect();
+    others.sort();
+    let mut names = vec![DEFAULT_PROFILE.to_string()];
+    names.extend(others);
+    names
+}
+
+/// Makes `name` the active profile, creating it if needed, and opens it on the next start too.
+pub fn switch_profile(name: &str) -> io::Result<()> {
+    if !is_valid_profile_name(name) {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("invalid profile name {:?}", name)));
+    }
+    let (Some(dirs), Some(paths)) = (project_dirs(), AppPaths::new(name)) else {
+        return Err(io::Error::new(io::ErrorKind::NotFound, "no home directory"));
+    };
+    // The profile's config directory is what makes it show up in `profiles`
+    fs::create_dir_all(&paths.config_dir)?;
+    fs::create_dir_all(dirs.config_dir())?;
+    fs::write(dirs.config_dir().join(ACTIVE_PROFILE_FILE), name)?;
+    *active().write().unwrap() = Some(Arc::new(paths));
+    Ok(())
+}
+
+/// Whether `name` can be used as a profile name, which is also a folder name.
+pub fn is_valid_profile_name(name: &str) -> bool {
+    !name.is_empty() && name == name.trim() && !name.starts_with('.') && !name.contains(['/', '\\', ':'])
+}
+
+fn project_dirs() -> Option<ProjectDirs> {
+    ProjectDirs::from("", "", "LyricsDownloader")
+}
+
+fn active() -> &'static RwLock<Option<Arc<AppPaths>>> {
+    static ACTIVE: OnceLock<RwLock<Option<Arc<AppPaths>>>> = OnceLock::new();
+    ACTIVE.get_or_init(|| {
+        let profile = project_dirs()
+            .and_then(|dirs| fs::read_to_string(dirs.config_dir().join(ACTIVE_PROFILE_FILE)).ok())
+            .map(|name| name.trim().to_string())
+            .filter(|name| is_valid_profile_name(name))
+            .unwrap_or_else(|| DEFAULT_PROFILE.to_string());
+        RwLock::new(AppPaths::new(&profile).map(Arc::new))
+    })
+}
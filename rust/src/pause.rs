@@ -0,0 +1,40 @@
+//! Pausing a run at the user's request, to get the bandwidth back for a while.
+//!
+//! A paused run finishes the files it is looking up, then its workers wait before starting the
+//! next one. Resuming lets them continue with the files they would have taken next, so nothing
+//! is skipped or done twice, and the run's counters and log carry on as before.
+
+use std::sync::{Condvar, Mutex};
+
+use crate::{CancellationToken, CANCEL_POLL_INTERVAL};
+
+#[derive(Default)]
+pub struct PauseSwitch {
+    paused: Mutex<bool>,
+    changed: Condvar,
+}
+
+impl PauseSwitch {
+    pub fn pause(&self) {
+        *self.paused.lock().unwrap() = true;
+    }
+
+    pub fn resume(&self) {
+        *self.paused.lock().unwrap() = false;
+        self.changed.notify_all();
+    }
+
+    pub fn is_paused(&self) -> bool {
+        *self.paused.lock().unwrap()
+    }
+
+    /// Blocks while the run is paused. Returns false if it was cancelled meanwhile.
+    pub fn wait(&self, cancel: &CancellationToken) -> bool {
+        let mut paused = self.paused.lock().unwrap();
+        while *paused && !cancel.is_cancelled() {
+            // Cancelling doesn't notify, so it is polled for
+            paused = self.changed.wait_timeout(paused, CANCEL_POLL_INTERVAL).unwrap().0;
+        }
+        !cancel.is_cancelled()
+    }
+}
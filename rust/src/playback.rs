@@ -0,0 +1,88 @@
+//! Playing a track inside the app with its synced lyrics scrolling along, to check a match by
+//! ear before the lyrics are embedded for good.
+//!
+//! Only built with the `playback` feature, as the audio output needs ALSA's development files on
+//! Linux. Opus files can't be decoded and are refused.
+
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use eframe::egui;
+use rodio::{Decoder, OutputStream, Sink, Source};
+
+use crate::lrc;
+use crate::progress::format_duration;
+
+/// Lines shown before and after the current one.
+const CONTEXT_LINES: usize = 3;
+
+pub struct Player {
+    pub file: PathBuf,
+    /// Kept alive for as long as the track plays; dropping it stops the sound.
+    _stream: OutputStream,
+    sink: Sink,
+    length: Option<Duration>,
+    /// Timed lines of the lyrics, with the `[offset:]` tag applied.
+    lines: Vec<(u32, String)>,
+}
+
+impl Player {
+    /// Starts playing `file`, showing `lyrics` along with it.
+    pub fn start(file: &Path, lyrics: &str) -> Result<Self, String> {
+        let (stream, handle) = OutputStream::try_default().map_err(|e| e.to_string())?;
+        let sink = Sink::try_new(&handle).map_err(|e| e.to_string())?;
+        let source = Decoder::new(BufReader::new(File::open(file).map_err(|e| e.to_string())?)).map_err(|e| e.to_string())?;
+        let length = source.total_duration();
+        sink.append(source);
+        // Players show the lyrics earlier by the offset
+        let offset = lrc::offset(lyrics);
+        let lines = lrc::timed_lines(lyrics)
+            .into_iter()
+            .map(|(millis, text)| ((millis as i64 - offset).max(0) as u32, text))
+            .collect();
+        Ok(Self { file: file.to_path_buf(), _stream: stream, sink, length, lines })
+    }
+
+    /// Shows the playback controls and the lines around the one being sung, the current line
+    /// highlighted. Returns false once the track was stopped or has ended.
+    pub fn show(&mut self, ui: &mut egui::Ui) -> bool {
+        let position = self.sink.get_pos();
+        let mut playing = !self.sink.empty();
+        ui.horizontal(|ui| {
+            let label = if self.sink.is_paused() { "▶ Play" } else { "⏸ Pause" };
+            if ui.button(label).clicked() {
+                if self.sink.is_paused() {
+                    self.sink.play();
+                } else {
+                    self.sink.pause();
+                }
+            }
+            if ui.button("⏹ Stop").clicked() {
+                self.sink.stop();
+                playing = false;
+            }
+            let mut time = format_duration(position);
+            if let Some(length) = self.length {
+                time += &format!(" / {}", format_duration(length));
+            }
+            ui.label(time);
+        });
+        let millis = position.as_millis() as u32;
+        let current = self.lines.iter().rposition(|(start, _)| *start <= millis);
+        let first = current.map_or(0, |current| current.saturating_sub(CONTEXT_LINES));
+        let last = (current.map_or(0, |current| current + 1) + CONTEXT_LINES).min(self.lines.len());
+        for (index, (_, text)) in self.lines.iter().enumerate().take(last).skip(first) {
+            if Some(index) == current {
+                ui.label(egui::RichText::new(text).strong().size(18.0));
+            } else {
+                ui.weak(text);
+            }
+        }
+        if playing {
+            ui.ctx().request_repaint_after(Duration::from_millis(100));
+        }
+        playing
+    }
+}
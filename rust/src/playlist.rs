@@ -0,0 +1,53 @@
+//! Reading M3U and M3U8 playlists, so a run can cover the tracks of a playlist instead of a
+//! whole folder.
+//!
+//! Entries are resolved relative to the playlist's own folder, and `#EXTINF` and other comment
+//! lines are skipped. Playlists written on Windows (backslashes, drive letters elsewhere in the
+//! library) and `file://` URLs are understood as well.
+
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Whether `path` names a playlist rather than a folder.
+pub fn is_playlist(path: &Path) -> bool {
+    path.extension().and_then(|ext| ext.to_str()).is_some_and(|ext| ext.eq_ignore_ascii_case("m3u") || ext.eq_ignore_ascii_case("m3u8"))
+}
+
+/// The files the playlist at `path` lists, in playlist order and without repeats.
+pub fn read(path: &Path) -> io::Result<Vec<PathBuf>> {
+    let bytes = fs::read(path)?;
+    // M3U8 is UTF-8; plain M3U files written by older players are usually Latin-1
+    let text = match String::from_utf8(bytes) {
+        Ok(text) => text,
+        Err(e) => e.into_bytes().iter().map(|&byte| byte as char).collect(),
+    };
+    let base = path.parent().unwrap_or(Path::new(""));
+    let mut seen = HashSet::new();
+    Ok(text
+        .lines()
+        .map(|line| line.trim_start_matches('\u{feff}').trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| resolve(base, line))
+        .filter(|file| seen.insert(file.clone()))
+        .collect())
+}
+
+/// The file an entry refers to, or None for entries that aren't local files, such as streams.
+fn resolve(base: &Path, entry: &str) -> Option<PathBuf> {
+    let entry = match entry.strip_prefix("file://") {
+        // "file:///C:/Music" has a slash before the drive letter
+        Some(url) => {
+            let path = urlencoding::decode(url).ok()?.into_owned();
+            match path.as_bytes() {
+                [b'/', _, b':', ..] => path[1..].to_string(),
+                _ => path,
+            }
+        }
+        None if entry.contains("://") => return None,
+        None => entry.to_string(),
+    };
+    let entry = if cfg!(windows) { entry } else { entry.replace('\\', "/") };
+    Some(base.join(entry))
+}
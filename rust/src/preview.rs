@@ -0,0 +1,171 @@
+//! Showing lyrics before and after they are saved.
+//!
+//! Lyrics are rendered with their timestamps and tags dimmed, so the text stands out. In review
+//! mode the write stage hands every file's lyrics to the GUI and waits for the user to accept or
+//! reject them, the same way `conflict` asks about existing lyrics. Lyrics decided about before
+//! aren't asked about again, see `decisions`. A whole album can be accepted or rejected at once,
+//! which answers its waiting files and those of it found later in the run.
+
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+
+use eframe::egui;
+use eframe::egui::text::LayoutJob;
+
+use crate::{RunContext, Track, CANCEL_POLL_INTERVAL};
+
+/// A file whose lyrics wait for the user to accept them.
+pub struct ReviewPrompt {
+    pub file: PathBuf,
+    pub album: Option<String>,
+    pub lyrics: String,
+    reply: mpsc::Sender<bool>,
+}
+
+impl ReviewPrompt {
+    /// Answers the prompt; with `accept` set the lyrics are saved.
+    pub fn answer(self, accept: bool) {
+        let _ = self.reply.send(accept);
+    }
+
+    /// The album the file is grouped by, ignoring case and surrounding whitespace.
+    pub fn album_key(&self) -> Option<String> {
+        self.album.as_deref().and_then(album_key)
+    }
+}
+
+/// Reviews of a run waiting for an answer, and the answers given to whole albums.
+#[derive(Default)]
+pub struct Reviews {
+    /// Oldest first.
+    pub prompts: VecDeque<ReviewPrompt>,
+    albums: HashMap<String, bool>,
+}
+
+impl Reviews {
+    /// Answers the waiting files of the album with `key`, and the files of it queued later.
+    pub fn answer_album(&mut self, key: &str, accept: bool) {
+        self.albums.insert(key.to_string(), accept);
+        let (answered, waiting): (VecDeque<_>, VecDeque<_>) =
+            self.prompts.drain(..).partition(|prompt| prompt.album_key().as_deref() == Some(key));
+        self.prompts = waiting;
+        for prompt in answered {
+            prompt.answer(accept);
+        }
+    }
+
+    /// Forgets the waiting files and album answers, at the end of a run.
+    pub fn clear(&mut self) {
+        self.prompts.clear();
+        self.albums.clear();
+    }
+}
+
+/// Shared between a run and the UI.
+pub type ReviewQueue = Arc<Mutex<Reviews>>;
+
+/// The file whose lyrics were found most recently in the run, and those lyrics.
+pub type LatestLyrics = Arc<Mutex<Option<(PathBuf, String)>>>;
+
+/// Queues `lyrics` of `track` for review and waits for the answer, which is remembered for tracks
+/// with a title and artist. Lyrics decided about before get the same answer without asking.
+/// Returns false if they were rejected or the run was cancelled while waiting.
+pub fn review(track: &Track, lyrics: &str, run: &RunContext) -> bool {
+    let file = &track.path;
+    let album = track.album.as_deref();
+    let remembered = match (&run.review_decisions, &track.artist, &track.title) {
+        (Some(decisions), Some(artist), Some(title)) => Some((decisions, artist.as_str(), title.as_str())),
+        _ => None,
+    };
+    if let Some(accept) = remembered.and_then(|(decisions, artist, title)| decisions.get(artist, title, album, lyrics)) {
+        let decision = if accept { "accepted" } else { "rejected" };
+        run.logs.lock().unwrap().push(format!("[INFO] The lyrics found for {} were {} before", file.display(), decision));
+        return accept;
+    }
+    let (reply, answer) = mpsc::channel();
+    let mut reviews = run.reviews.lock().unwrap();
+    // Checked under the same lock the album is answered with, so no file of it is left waiting
+    let album_answer = album.and_then(album_key).and_then(|key| reviews.albums.get(&key).copied());
+    let accept = match album_answer {
+        Some(accept) => {
+            drop(reviews);
+            let decision = if accept { "accepted" } else { "rejected" };
+            run.logs.lock().unwrap().push(format!("[INFO] The lyrics found for {} were {} with their album", file.display(), decision));
+            accept
+        }
+        None => {
+            let album = album.map(str::to_string);
+            reviews.prompts.push_back(ReviewPrompt { file: file.to_path_buf(), album, lyrics: lyrics.to_string(), reply });
+            drop(reviews);
+            loop {
+                match answer.recv_timeout(CANCEL_POLL_INTERVAL) {
+                    Ok(accept) => break accept,
+                    Err(RecvTimeoutError::Timeout) if !run.cancel.is_cancelled() => {}
+                    Err(_) => return false,
+                }
+            }
+        }
+    };
+    if let Some((decisions, artist, title)) = remembered {
+        if let Err(e) = decisions.record(artist, title, album, lyrics, accept) {
+            run.logs.lock().unwrap().push(format!("⚠ Failed to save the review decisions: {}", e));
+        }
+    }
+    accept
+}
+
+fn album_key(album: &str) -> Option<String> {
+    Some(album.trim().to_lowercase()).filter(|key| !key.is_empty())
+}
+
+/// Shows `lyrics` line by line, with the leading `[mm:ss.xx]` timestamps and `[ar: ...]` style
+/// tags of each line dimmed.
+pub fn lyrics_ui(ui: &mut egui::Ui, lyrics: &str) {
+    let font = egui::TextStyle::Body.resolve(ui.style());
+    let (dimmed, normal) = (ui.visuals().weak_text_color(), ui.visuals().text_color());
+    for line in lyrics.lines() {
+        let (tags, text) = split_tags(line);
+        let mut job = LayoutJob::default();
+        job.append(tags, 0.0, egui::TextFormat::simple(font.clone(), dimmed));
+        job.append(text, 0.0, egui::TextFormat::simple(font.clone(), normal));
+        ui.label(job);
+    }
+}
+
+/// Splits a line into its leading bracketed tags and the rest.
+fn split_tags(line: &str) -> (&str, &str) {
+    let mut end = 0;
+    while let Some(close) = line[end..].strip_prefix('[').and_then(|rest| rest.find(']')) {
+        end += close + 2;
+    }
+    line.split_at(end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prompt(file: &str, album: Option<&str>) -> (ReviewPrompt, mpsc::Receiver<bool>) {
+        let (reply, answer) = mpsc::channel();
+        let album = album.map(str::to_string);
+        (ReviewPrompt { file: PathBuf::from(file), album, lyrics: String::new(), reply }, answer)
+    }
+
+    #[test]
+    fn answering_an_album_answers_only_its_files() {
+        let mut reviews = Reviews::default();
+        let (first, first_answer) = prompt("a/1.mp3", Some("Abbey Road"));
+        let (other, other_answer) = prompt("b/1.mp3", Some("Let It Be"));
+        let (second, second_answer) = prompt("a/2.mp3", Some(" abbey road"));
+        reviews.prompts.extend([first, other, second]);
+
+        reviews.answer_album("abbey road", false);
+        assert_eq!(first_answer.try_recv(), Ok(false));
+        assert_eq!(second_answer.try_recv(), Ok(false));
+        assert!(other_answer.try_recv().is_err());
+        assert_eq!(reviews.prompts.len(), 1);
+        assert_eq!(reviews.albums.get("abbey road"), Some(&false));
+    }
+}
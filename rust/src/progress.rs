@@ -0,0 +1,91 @@
+//! Progress of the run in progress, shared between the pipeline and the GUI.
+//!
+//! The folder scan counts the audio files it finds while the pipeline already works on them, so
+//! the total is only known once the scan has finished. Until then the GUI shows how many files
+//! were found so far.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[derive(Default)]
+pub struct ProgressState {
+    found: AtomicUsize,
+    scan_finished: AtomicBool,
+    done: AtomicUsize,
+    current: Mutex<Option<PathBuf>>,
+    started: Mutex<Option<Instant>>,
+}
+
+/// A consistent view of a `ProgressState`.
+pub struct Progress {
+    pub done: usize,
+    /// Number of files found by the scan so far.
+    pub found: usize,
+    /// Whether the scan has finished, so `found` is the total.
+    pub scan_finished: bool,
+    /// File most recently picked up by the pipeline.
+    pub current: Option<PathBuf>,
+    /// Time until all files are done at the pace so far, once the total is known.
+    pub eta: Option<Duration>,
+}
+
+impl Progress {
+    /// Share of the files that are done, once the total is known.
+    pub fn fraction(&self) -> Option<f32> {
+        match self.found {
+            0 => self.scan_finished.then_some(1.0),
+            found => self.scan_finished.then(|| self.done as f32 / found as f32),
+        }
+    }
+}
+
+impl ProgressState {
+    /// Starts tracking a new run.
+    pub fn reset(&self) {
+        self.found.store(0, Ordering::SeqCst);
+        self.scan_finished.store(false, Ordering::SeqCst);
+        self.done.store(0, Ordering::SeqCst);
+        *self.current.lock().unwrap() = None;
+        *self.started.lock().unwrap() = Some(Instant::now());
+    }
+
+    pub fn found_file(&self) {
+        self.found.fetch_add(1, Ordering::SeqCst);
+    }
+
+    pub fn finish_scan(&self) {
+        self.scan_finished.store(true, Ordering::SeqCst);
+    }
+
+    pub fn start_file(&self, path: &Path) {
+        *self.current.lock().unwrap() = Some(path.to_path_buf());
+    }
+
+    pub fn finish_file(&self) {
+        self.done.fetch_add(1, Ordering::SeqCst);
+    }
+
+    pub fn snapshot(&self) -> Progress {
+        let done = self.done.load(Ordering::SeqCst);
+        let found = self.found.load(Ordering::SeqCst);
+        let scan_finished = self.scan_finished.load(Ordering::SeqCst);
+        let elapsed = self.started.lock().unwrap().map(|started| started.elapsed());
+        let eta = match elapsed {
+            Some(elapsed) if scan_finished && done > 0 => Some(elapsed.mul_f64(found.saturating_sub(done) as f64 / done as f64)),
+            _ => None,
+        };
+        Progress { done, found, scan_finished, current: self.current.lock().unwrap().clone(), eta }
+    }
+}
+
+/// Formats `duration` for display, such as `1h 05m`, `3m 20s` or `45s`.
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match (secs / 3600, secs / 60 % 60, secs % 60) {
+        (0, 0, s) => format!("{}s", s),
+        (0, m, s) => format!("{}m {:02}s", m, s),
+        (h, m, _) => format!("{}h {:02}m", h, m),
+    }
+}
@@ -0,0 +1,109 @@
+//! Provenance tags written next to embedded lyrics.
+//!
+//! They record which provider the lyrics came from, the provider's id for them, when they were
+//! fetched and by which version of the app, so later runs and other tools can tell where lyrics
+//! came from and whether they should be refreshed. In ID3v2 tags they are stored as TXXX frames,
+//! in Vorbis comments as fields of the same name. Next to `.lrc` files the same information is
+//! written to a `.lrc.json` sidecar.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use lofty::{ItemKey, Tag};
+use serde::Serialize;
+
+use crate::disk;
+
+const SOURCE: &str = "LYRICS_SOURCE";
+const SOURCE_ID: &str = "LYRICS_SOURCE_ID";
+const FETCHED_AT: &str = "LYRICS_FETCHED_AT";
+const FETCHED_BY: &str = "LYRICS_FETCHED_BY";
+
+/// Names of the provenance fields in tags.
+pub const KEYS: [&str; 4] = [SOURCE, SOURCE_ID, FETCHED_AT, FETCHED_BY];
+
+/// Where a track's lyrics came from.
+#[derive(Clone)]
+pub struct Provenance {
+    pub provider: &'static str,
+    /// The provider's id for the lyrics, such as lrclib's record id.
+    pub source_id: Option<u64>,
+    /// Length of the recording the provider's lyrics were synced to, in seconds.
+    pub source_duration: Option<f64>,
+    pub fetched_at: String,
+}
+
+/// Contents of a `.lrc.json` sidecar.
+#[derive(Serialize)]
+struct Sidecar<'a> {
+    source: &'a str,
+    source_id: Option<u64>,
+    fetched_at: &'a str,
+    fetched_by: String,
+    /// Provider's duration minus the audio file's, in seconds. Large differences usually mean
+    /// the lyrics belong to another version of the song.
+    duration_delta_secs: Option<f64>,
+}
+
+impl Provenance {
+    /// Provenance of lyrics fetched from `provider` just now.
+    pub fn new(provider: &'static str, source_id: Option<u64>, source_duration: Option<f64>) -> Self {
+        let fetched_at = chrono::Local::now().format("%Y-%m-%dT%H:%M:%S").to_string();
+        Self { provider, source_id, source_duration, fetched_at }
+    }
+
+    /// Writes the provenance fields into `tag`, replacing any written by an earlier run.
+    pub fn write_to(&self, tag: &mut Tag) {
+        clear(tag);
+        tag.insert_text(key(SOURCE), self.provider.to_string());
+        if let Some(id) = self.source_id {
+            tag.insert_text(key(SOURCE_ID), id.to_string());
+        }
+        tag.insert_text(key(FETCHED_AT), self.fetched_at.clone());
+        tag.insert_text(key(FETCHED_BY), fetched_by());
+    }
+
+    /// Writes the provenance to a `.lrc.json` sidecar next to the audio file at `audio`, whose
+    /// length is `duration`.
+    pub fn write_sidecar(&self, audio: &Path, duration: Option<Duration>, durable: bool) -> io::Result<()> {
+        let delta = self.source_duration.zip(duration).map(|(source, duration)| {
+            ((source - duration.as_secs_f64()) * 100.0).round() / 100.0
+        });
+        let sidecar = Sidecar {
+            source: self.provider,
+            source_id: self.source_id,
+            fetched_at: &self.fetched_at,
+            fetched_by: fetched_by(),
+            duration_delta_secs: delta,
+        };
+        let path = sidecar_path(audio);
+        fs::write(&path, serde_json::to_string_pretty(&sidecar)?)?;
+        if durable {
+            disk::sync_file(&path)?;
+            disk::sync_parent_dir(&path);
+        }
+        Ok(())
+    }
+}
+
+fn fetched_by() -> String {
+    format!("LyricsDownloader {}", env!("CARGO_PKG_VERSION"))
+}
+
+/// Where the `.lrc.json` sidecar of the audio file at `audio` is written.
+pub fn sidecar_path(audio: &Path) -> PathBuf {
+    audio.with_extension("lrc.json")
+}
+
+/// Removes the provenance fields from `tag`, so they don't outlive the lyrics they describe.
+pub fn clear(tag: &mut Tag) {
+    for name in KEYS {
+        tag.remove_key(&key(name));
+    }
+}
+
+fn key(name: &str) -> ItemKey {
+    ItemKey::Unknown(name.to_string())
+}
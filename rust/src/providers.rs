@@ -0,0 +1,161 @@
+//! Sources of lyrics, asked one after another until one has lyrics for the track.
+//!
+//! lrclib is asked first by default. The order, and which providers are asked at all, is set per
+//! profile in the config file, see `AppConfig::providers`. Unless the profile keeps that order,
+//! providers covering the script of a track's title are asked first, see `for_query`.
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::AppConfig;
+use crate::failure::Failure;
+use crate::genius::Genius;
+use crate::matching;
+use crate::netease::NetEase;
+use crate::script::{self, Script};
+use crate::{lrclib_request, settle, CancellationToken, LyricsResult, ProviderResponse, RunContext};
+
+/// The track to look up.
+pub struct Query<'a> {
+    pub title: &'a str,
+    pub artist: &'a str,
+    pub album: Option<&'a str>,
+    pub duration: Option<Duration>,
+}
+
+pub trait LyricsProvider {
+    /// Name recorded in provenance and manifests, also used to key the rate limiter and the
+    /// lookup cache.
+    fn name(&self) -> &'static str;
+
+    /// Whether the provider has synced lyrics. Providers without are only asked when plain
+    /// lyrics are accepted.
+    fn has_synced_lyrics(&self) -> bool {
+        true
+    }
+
+    /// Looks up the lyrics of `query`. Returns None if the provider has none.
+    ///
+    /// Requests go through `provider_request`, so they share the run's rate limiting, concurrency
+    /// and network statistics.
+    fn fetch(&self, query: &Query, run: &RunContext, cancel: &CancellationToken) -> Result<Option<LyricsResult>, Failure>;
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProviderKind {
+    Lrclib,
+    Genius,
+    #[serde(rename = "netease")]
+    NetEase,
+}
+
+impl ProviderKind {
+    pub const ALL: [ProviderKind; 3] = [ProviderKind::Lrclib, ProviderKind::Genius, ProviderKind::NetEase];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ProviderKind::Lrclib => "lrclib",
+            ProviderKind::Genius => "Genius (plain lyrics only)",
+            ProviderKind::NetEase => "NetEase Cloud Music (Chinese, Japanese and Korean music)",
+        }
+    }
+
+    /// Whether the provider is asked if the config doesn't say. Providers that send the user's
+    /// track names somewhere new are off until the user turns them on.
+    pub fn enabled_by_default(self) -> bool {
+        match self {
+            ProviderKind::Lrclib | ProviderKind::Genius => true,
+            ProviderKind::NetEase => false,
+        }
+    }
+
+    /// Whether the provider specializes in music whose titles are in `script`, so it is asked
+    /// before the others for such tracks.
+    fn covers(self, script: Script) -> bool {
+        match self {
+            ProviderKind::NetEase => script == Script::Cjk,
+            ProviderKind::Lrclib | ProviderKind::Genius => false,
+        }
+    }
+
+    /// The provider, or None if it can't be used with `config`, such as Genius without an API
+    /// token.
+    fn provider(self, config: &AppConfig) -> Option<Box<dyn LyricsProvider>> {
+        match self {
+            ProviderKind::Lrclib => Some(Box::new(Lrclib)),
+            ProviderKind::Genius => Genius::new(&config.genius_token).map(|genius| Box::new(genius) as Box<dyn LyricsProvider>),
+            ProviderKind::NetEase => Some(Box::new(NetEase)),
+        }
+    }
+}
+
+/// Position of a provider in the lookup order.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ProviderSetting {
+    pub kind: ProviderKind,
+    pub enabled: bool,
+}
+
+/// The providers of `config` that are enabled and usable, in the order they are asked for
+/// `query`: those covering the script of its title first, unless `AppConfig::keep_provider_order`
+/// is set, and otherwise in the configured order.
+pub fn for_query(config: &AppConfig, query: &Query) -> Vec<Box<dyn LyricsProvider>> {
+    let mut providers = config.providers();
+    if let Some(script) = script::of_track(query.title, query.artist).filter(|_| !config.keep_provider_order) {
+        providers.sort_by_key(|setting| !setting.kind.covers(script));
+    }
+    providers.into_iter().filter(|setting| setting.enabled).filter_map(|setting| setting.kind.provider(config)).collect()
+}
+
+/// lrclib.net, which has synced lyrics for a large part of popular music.
+pub struct Lrclib;
+
+impl LyricsProvider for Lrclib {
+    fn name(&self) -> &'static str {
+        crate::LRCLIB
+    }
+
+    /// When the track's duration is known, lrclib's get endpoint is asked first for the recording
+    /// of exactly that length (and album, if known), so live versions, remixes and covers of the
+    /// same song aren't picked by mistake. Only if it has no such recording is a search result
+    /// used, see `best_result`.
+    fn fetch(&self, query: &Query, run: &RunContext, cancel: &CancellationToken) -> Result<Option<LyricsResult>, Failure> {
+        let params = format!("track_name={}&artist_name={}", urlencoding::encode(query.title), urlencoding::encode(query.artist));
+        if let Some(duration) = query.duration {
+            let mut url = format!("https://lrclib.net/api/get?{}&duration={}", params, duration.as_secs_f64().round());
+            if let Some(album) = query.album {
+                url.push_str(&format!("&album_name={}", urlencoding::encode(album)));
+            }
+            match lrclib_request(url, true, run, cancel)? {
+                ProviderResponse::NotFound => run.concurrency.record_success(),
+                response => return Ok(settle(self.name(), response, run)?.and_then(|results| results.into_iter().next())),
+            }
+        }
+        let response = lrclib_request(format!("https://lrclib.net/api/search?{}", params), false, run, cancel)?;
+        Ok(settle(self.name(), response, run)?.and_then(|results| best_result(self.name(), results, query, run)))
+    }
+}
+
+/// The search result of `provider` that matches the track best, see `matching::best`. Returns
+/// None, logging the closest result, if even that scores below the profile's
+/// `MatchRules::min_score`.
+pub fn best_result(provider: &str, results: Vec<LyricsResult>, query: &Query, run: &RunContext) -> Option<LyricsResult> {
+    let (result, score) = matching::best(results, query)?;
+    let rules = run.app_config.match_rules;
+    if !rules.accepts(score) {
+        run.logs.lock().unwrap().push(format!(
+            "↷ Closest {} result for {} by {} is {} by {}, scoring {:.0}% (at least {}% needed)",
+            provider,
+            query.title,
+            query.artist,
+            result.track_name.as_deref().unwrap_or("?"),
+            result.artist_name.as_deref().unwrap_or("?"),
+            score * 100.0,
+            rules.min_score
+        ));
+        return None;
+    }
+    Some(result)
+}
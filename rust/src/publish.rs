@@ -0,0 +1,154 @@
+//! Contributing lyrics to lrclib.
+//!
+//! lrclib accepts new lyrics without an account, but every submission needs a publish token:
+//! the app asks for a challenge, a prefix and a target, and searches for a nonce whose SHA-256
+//! hash of prefix and nonce is at most the target. That takes a few seconds to a minute, so
+//! publishing runs on a worker thread. Tracks lrclib already has lyrics for are left alone.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::network::{http_client, runtime};
+use crate::{lrc, read_track, LyricsResult};
+
+const API: &str = "https://lrclib.net/api";
+
+/// Lyrics of a track, ready to be published.
+pub struct Submission {
+    pub file: PathBuf,
+    pub title: String,
+    pub artist: String,
+    pub album: String,
+    pub duration: Duration,
+    pub lyrics: String,
+}
+
+impl Submission {
+    /// Collects what lrclib needs to know about the audio file at `file`. Fails with the missing
+    /// field if its tags lack the title, artist or album, or its length can't be read.
+    pub fn new(file: &Path, lyrics: &str) -> Result<Self, String> {
+        let track = read_track(file.to_path_buf());
+        let missing = |field: &str| format!("{} has no {}, which lrclib needs", file.display(), field);
+        Ok(Self {
+            file: file.to_path_buf(),
+            title: track.title.ok_or_else(|| missing("title"))?,
+            artist: track.artist.ok_or_else(|| missing("artist"))?,
+            album: track.album.ok_or_else(|| missing("album"))?,
+            duration: track.duration.ok_or_else(|| missing("length"))?,
+            lyrics: lyrics.to_string(),
+        })
+    }
+
+    /// The body of the publish request. Synced lyrics are sent without tags, along with their
+    /// plain text, as lrclib stores both.
+    fn request(&self) -> PublishRequest<'_> {
+        let (plain_lyrics, synced_lyrics) = match lrc::is_synced(&self.lyrics) {
+            true => {
+                let lines = lrc::timed_lines(&self.lyrics);
+                let plain = lines.iter().map(|(_, text)| text.as_str()).collect::<Vec<_>>().join("\n");
+                (plain, lrc::format_timed_lines(&lines))
+            }
+            false => (self.lyrics.trim().to_string(), String::new()),
+        };
+        PublishRequest {
+            track_name: &self.title,
+            artist_name: &self.artist,
+            album_name: &self.album,
+            duration: self.duration.as_secs_f64().round(),
+            plain_lyrics,
+            synced_lyrics,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PublishRequest<'a> {
+    track_name: &'a str,
+    artist_name: &'a str,
+    album_name: &'a str,
+    duration: f64,
+    plain_lyrics: String,
+    synced_lyrics: String,
+}
+
+#[derive(Deserialize)]
+struct Challenge {
+    prefix: String,
+    /// Hex encoded.
+    target: String,
+}
+
+/// What came of publishing a submission.
+pub enum Published {
+    Published,
+    /// lrclib already has lyrics of the kind submitted for the track.
+    AlreadyThere,
+}
+
+/// Publishes `submission` to lrclib, unless it already has lyrics for the track.
+pub fn publish(submission: &Submission) -> Result<Published, String> {
+    let request = submission.request();
+    if has_lyrics(submission, !request.synced_lyrics.is_empty())? {
+        return Ok(Published::AlreadyThere);
+    }
+    let challenge: Challenge = runtime().block_on(async {
+        let response = http_client().post(format!("{}/request-challenge", API)).send().await.map_err(|e| e.to_string())?;
+        response.error_for_status().map_err(|e| e.to_string())?.json().await.map_err(|e| e.to_string())
+    })?;
+    let nonce = solve(&challenge.prefix, &challenge.target)?;
+    runtime().block_on(async {
+        let response = http_client()
+            .post(format!("{}/publish", API))
+            .header("X-Publish-Token", format!("{}:{}", challenge.prefix, nonce))
+            .json(&request)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        match response.status() {
+            status if status.is_success() => Ok(Published::Published),
+            status => Err(format!("lrclib answered {}: {}", status, response.text().await.unwrap_or_default())),
+        }
+    })
+}
+
+/// Whether lrclib has synced lyrics for the track, or any lyrics if `synced` isn't set.
+fn has_lyrics(submission: &Submission, synced: bool) -> Result<bool, String> {
+    let url = format!(
+        "{}/get?track_name={}&artist_name={}&album_name={}&duration={}",
+        API,
+        urlencoding::encode(&submission.title),
+        urlencoding::encode(&submission.artist),
+        urlencoding::encode(&submission.album),
+        submission.duration.as_secs_f64().round()
+    );
+    runtime().block_on(async {
+        let response = http_client().get(url).send().await.map_err(|e| e.to_string())?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(false);
+        }
+        let found: LyricsResult = response.error_for_status().map_err(|e| e.to_string())?.json().await.map_err(|e| e.to_string())?;
+        let has = |lyrics: &Option<String>| lyrics.as_deref().is_some_and(|lyrics| !lyrics.trim().is_empty());
+        Ok(has(&found.synced_lyrics) || (!synced && has(&found.plain_lyrics)))
+    })
+}
+
+/// Finds the first nonce whose SHA-256 hash of `prefix` followed by the nonce is at most the
+/// hex encoded `target`, compared as big-endian numbers.
+fn solve(prefix: &str, target: &str) -> Result<u64, String> {
+    let target = decode_hex(target).ok_or("lrclib sent an invalid challenge")?;
+    (0..u64::MAX)
+        .find(|nonce| Sha256::digest(format!("{}{}", prefix, nonce)).as_slice() <= target.as_slice())
+        .ok_or_else(|| "no nonce solves lrclib's challenge".to_string())
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok()).collect()
+}
@@ -0,0 +1,93 @@
+//! Several library folders processed in one run.
+//!
+//! Folders queued next to the selected one are processed with it, one after another or all at
+//! once. Each is a run of its own with its own manifest and history entry, so per-folder
+//! statistics come for free; `QueuedFolder` tracks them live and the totals are added up here.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use eframe::egui;
+
+use crate::failure::{self, Failure};
+use crate::history::RunSummary;
+use crate::progress::ProgressState;
+
+/// Counters of one folder of a queue run, shared between its pipeline and the GUI.
+pub struct QueuedFolder {
+    pub folder: PathBuf,
+    pub scanned: Arc<AtomicUsize>,
+    pub written: Arc<AtomicUsize>,
+    pub progress: Arc<ProgressState>,
+    /// Set once the folder is done.
+    pub summary: Mutex<Option<RunSummary>>,
+}
+
+impl QueuedFolder {
+    pub fn new(folder: &Path) -> Self {
+        Self {
+            folder: folder.to_path_buf(),
+            scanned: Arc::default(),
+            written: Arc::default(),
+            progress: Arc::default(),
+            summary: Mutex::new(None),
+        }
+    }
+}
+
+/// Files scanned and lyrics written in all folders of a queue run so far.
+pub fn totals(folders: &[Arc<QueuedFolder>]) -> (usize, usize) {
+    folders.iter().fold((0, 0), |(scanned, written), queued| {
+        (scanned + queued.scanned.load(Ordering::Relaxed), written + queued.written.load(Ordering::Relaxed))
+    })
+}
+
+/// Failures of all `summaries` added up, by reason.
+pub fn total_failures(summaries: &[RunSummary]) -> BTreeMap<Failure, usize> {
+    let mut failures = BTreeMap::new();
+    for (&failure, &count) in summaries.iter().flat_map(|summary| &summary.failures) {
+        *failures.entry(failure).or_default() += count;
+    }
+    failures
+}
+
+/// Logs what all folders of a queue run came to together.
+pub fn log_totals(logs: &mut Vec<String>, summaries: &[RunSummary]) {
+    let scanned: usize = summaries.iter().map(|summary| summary.scanned).sum();
+    let written: usize = summaries.iter().map(|summary| summary.written).sum();
+    logs.push(format!("\n[INFO] All {} folders: lyrics for {} of {} files.", summaries.len(), written, scanned));
+    if let Some(failures) = failure::describe_counts(&total_failures(summaries)) {
+        logs.push(format!("[INFO] Failures in all folders: {}.", failures));
+    }
+}
+
+/// Shows one line per folder of a queue run: its progress while it is processed, and what it
+/// came to once it is done.
+pub fn status_ui(ui: &mut egui::Ui, folders: &[Arc<QueuedFolder>]) {
+    for queued in folders {
+        let mut text = format!("{}: ", queued.folder.display());
+        match &*queued.summary.lock().unwrap() {
+            Some(summary) => {
+                text += &format!("{} scanned, {} written", summary.scanned, summary.written);
+                if let Some(failures) = failure::describe_counts(&summary.failures) {
+                    text += &format!(" · {}", failures);
+                }
+                if summary.cancelled {
+                    text += " · cancelled";
+                }
+            }
+            None => {
+                let progress = queued.progress.snapshot();
+                match progress.fraction() {
+                    Some(fraction) => text += &format!("{}/{} files ({:.0}%)", progress.done, progress.found, fraction * 100.0),
+                    None if progress.found > 0 => text += &format!("scanning, {} files found", progress.found),
+                    None => text += "waiting",
+                }
+                text += &format!(" · {} written", queued.written.load(Ordering::Relaxed));
+            }
+        }
+        ui.label(text);
+    }
+}
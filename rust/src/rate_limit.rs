@@ -0,0 +1,133 @@
+//! Rate limiting of requests to lyrics providers.
+//!
+//! Every provider gets its own `RateLimiter` from the `RateLimiters` registry, so a slow or
+//! strict API only throttles requests to itself. On top of that, one more limiter caps the
+//! requests to all providers together.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::{CancellationToken, CANCEL_POLL_INTERVAL};
+
+/// Request rate used for providers that have not been configured explicitly.
+pub const DEFAULT_REQUESTS_PER_SECOND: f64 = 5.0;
+
+/// Default cap on the requests to all providers together.
+pub const DEFAULT_TOTAL_REQUESTS_PER_SECOND: f64 = 8.0;
+
+/// Spaces out the requests to a single provider.
+pub struct RateLimiter {
+    state: Mutex<LimiterState>,
+}
+
+struct LimiterState {
+    interval: Duration,
+    next_slot: Instant,
+    backoff_until: Option<Instant>,
+}
+
+impl RateLimiter {
+    pub fn new(requests_per_second: f64) -> Self {
+        Self {
+            state: Mutex::new(LimiterState {
+                interval: interval_for(requests_per_second),
+                next_slot: Instant::now(),
+                backoff_until: None,
+            }),
+        }
+    }
+
+    pub fn set_rate(&self, requests_per_second: f64) {
+        self.state.lock().unwrap().interval = interval_for(requests_per_second);
+    }
+
+    /// Holds back all requests to this provider for `duration`, e.g. after an HTTP 429.
+    pub fn back_off(&self, duration: Duration) {
+        let until = Instant::now() + duration;
+        let mut state = self.state.lock().unwrap();
+        state.backoff_until = Some(state.backoff_until.map_or(until, |current| current.max(until)));
+    }
+
+    pub fn is_backing_off(&self) -> bool {
+        let state = self.state.lock().unwrap();
+        state.backoff_until.is_some_and(|until| until > Instant::now())
+    }
+
+    /// Blocks until the next request may be sent.
+    ///
+    /// Returns false if `cancel` fired while waiting, in which case no request should be made.
+    pub fn acquire(&self, cancel: &CancellationToken) -> bool {
+        let slot = {
+            let mut state = self.state.lock().unwrap();
+            let mut slot = state.next_slot.max(Instant::now());
+            if let Some(until) = state.backoff_until {
+                slot = slot.max(until);
+            }
+            state.next_slot = slot + state.interval;
+            slot
+        };
+        loop {
+            if cancel.is_cancelled() {
+                return false;
+            }
+            let now = Instant::now();
+            if now >= slot {
+                return true;
+            }
+            thread::sleep((slot - now).min(CANCEL_POLL_INTERVAL));
+        }
+    }
+}
+
+fn interval_for(requests_per_second: f64) -> Duration {
+    Duration::from_secs_f64(1.0 / requests_per_second.max(0.01))
+}
+
+/// Registry of rate limiters keyed by provider name, plus the limiter shared by all providers.
+pub struct RateLimiters {
+    limiters: Mutex<HashMap<String, Arc<RateLimiter>>>,
+    total: RateLimiter,
+}
+
+impl Default for RateLimiters {
+    fn default() -> Self {
+        Self { limiters: Mutex::default(), total: RateLimiter::new(DEFAULT_TOTAL_REQUESTS_PER_SECOND) }
+    }
+}
+
+impl RateLimiters {
+    /// Returns the limiter for `provider`, creating one with the default rate if needed.
+    pub fn get(&self, provider: &str) -> Arc<RateLimiter> {
+        let mut limiters = self.limiters.lock().unwrap();
+        let limiter = limiters
+            .entry(provider.to_string())
+            .or_insert_with(|| Arc::new(RateLimiter::new(DEFAULT_REQUESTS_PER_SECOND)));
+        Arc::clone(limiter)
+    }
+
+    pub fn set_rate(&self, provider: &str, requests_per_second: f64) {
+        self.get(provider).set_rate(requests_per_second);
+    }
+
+    /// Caps the requests to all providers together, or lifts the cap if `requests_per_second`
+    /// is 0.
+    pub fn set_total_rate(&self, requests_per_second: f64) {
+        self.total.state.lock().unwrap().interval = match requests_per_second > 0.0 {
+            true => interval_for(requests_per_second),
+            false => Duration::ZERO,
+        };
+    }
+
+    /// Blocks until a request to `provider` may be sent under both its own limit and the total
+    /// one. Returns false if `cancel` fired while waiting.
+    pub fn acquire(&self, provider: &str, cancel: &CancellationToken) -> bool {
+        self.get(provider).acquire(cancel) && self.total.acquire(cancel)
+    }
+
+    /// Returns true if any provider is currently backing off.
+    pub fn any_backing_off(&self) -> bool {
+        self.limiters.lock().unwrap().values().any(|limiter| limiter.is_backing_off())
+    }
+}
@@ -0,0 +1,102 @@
+//! Sanitized log export, for attaching logs to bug reports.
+//!
+//! While a run reads its files, their paths, titles and artists are remembered. On export every
+//! occurrence is replaced with a placeholder such as `<file-1a2b3c4d>.flac` or `<artist-5e6f7a8b>`,
+//! derived from a hash of the value so the same file stays recognizable across lines. The
+//! library folder and the home directory become `<library>` and `<home>`.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+
+use directories::BaseDirs;
+use sha2::{Digest, Sha256};
+
+/// Values shorter than this are not redacted, as they would match inside ordinary words.
+const MIN_LEN: usize = 3;
+
+#[derive(Default)]
+pub struct Redactions {
+    replacements: Mutex<HashMap<String, String>>,
+}
+
+impl Redactions {
+    pub fn clear(&self) {
+        self.replacements.lock().unwrap().clear();
+    }
+
+    /// Remembers the path of a track, its `.lrc` sidecar, and its title and artist.
+    pub fn add_track(&self, path: &Path, title: Option<&str>, artist: Option<&str>) {
+        let placeholder = placeholder("file", &path.to_string_lossy());
+        let ext = path.extension().map(|ext| format!(".{}", ext.to_string_lossy())).unwrap_or_default();
+        let lrc = path.with_extension("lrc");
+        let mut replacements = self.replacements.lock().unwrap();
+        let mut add = |value: Option<String>, replacement: String| {
+            if let Some(value) = value.filter(|value| value.chars().count() >= MIN_LEN) {
+                replacements.insert(value, replacement);
+            }
+        };
+        add(Some(path.to_string_lossy().into_owned()), format!("{}{}", placeholder, ext));
+        add(Some(lrc.to_string_lossy().into_owned()), format!("{}.lrc", placeholder));
+        add(path.file_name().map(|name| name.to_string_lossy().into_owned()), format!("{}{}", placeholder, ext));
+        add(lrc.file_name().map(|name| name.to_string_lossy().into_owned()), format!("{}.lrc", placeholder));
+        add(path.file_stem().map(|stem| stem.to_string_lossy().into_owned()), placeholder);
+        if let Some(title) = title {
+            add(Some(title.to_string()), self::placeholder("title", title));
+        }
+        if let Some(artist) = artist {
+            add(Some(artist.to_string()), self::placeholder("artist", artist));
+        }
+    }
+
+    /// Returns `lines` as text with every remembered value, `folder` and the home directory
+    /// replaced by placeholders.
+    pub fn sanitize(&self, lines: &[String], folder: Option<&Path>) -> String {
+        let mut replacements: Vec<(String, String)> =
+            self.replacements.lock().unwrap().iter().map(|(value, replacement)| (value.clone(), replacement.clone())).collect();
+        if let Some(folder) = folder {
+            replacements.push((folder.to_string_lossy().into_owned(), "<library>".to_string()));
+        }
+        if let Some(dirs) = BaseDirs::new() {
+            replacements.push((dirs.home_dir().to_string_lossy().into_owned(), "<home>".to_string()));
+        }
+        // Full paths go before the file names and folders they contain
+        replacements.sort_by_key(|(value, _)| std::cmp::Reverse(value.len()));
+        let mut text = String::new();
+        for line in lines {
+            let mut line = line.clone();
+            for (value, replacement) in &replacements {
+                line = replace_whole(&line, value, replacement);
+            }
+            text.push_str(line.trim_start_matches('\n'));
+            text.push('\n');
+        }
+        text
+    }
+}
+
+fn placeholder(kind: &str, value: &str) -> String {
+    let hash = Sha256::digest(value.as_bytes());
+    format!("<{}-{:02x}{:02x}{:02x}{:02x}>", kind, hash[0], hash[1], hash[2], hash[3])
+}
+
+/// Replaces the occurrences of `value` in `line` that are not part of a longer word.
+fn replace_whole(line: &str, value: &str, replacement: &str) -> String {
+    let mut result = String::with_capacity(line.len());
+    let mut rest = line;
+    while let Some(start) = rest.find(value) {
+        let end = start + value.len();
+        let before = rest[..start].chars().next_back();
+        let after = rest[end..].chars().next();
+        let is_word = |c: Option<char>| c.is_some_and(char::is_alphanumeric);
+        result.push_str(&rest[..start]);
+        if is_word(before) || is_word(after) {
+            result.push_str(value);
+        } else {
+            result.push_str(replacement);
+        }
+        rest = &rest[end..];
+    }
+    result.push_str(rest);
+    result
+}
@@ -0,0 +1,335 @@
+//! Per-file results of the run in progress, shown as a sortable table next to the log.
+//!
+//! Rows are added as the manifest records each file's outcome, together with the title and
+//! artist read from the file, if it got that far. The rows can be exported as a CSV or JSON
+//! report, see `RunResults::report`.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use eframe::egui;
+use serde::Serialize;
+
+use crate::failure::Failure;
+use crate::manual::ManualSearch;
+use crate::manifest::{ManifestEntry, Outcome};
+use crate::{output_path, OutputMode};
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Status {
+    Found,
+    /// Existing lyrics were kept, or the file was left alone on purpose.
+    Skipped,
+    NotFound,
+    Error(Failure),
+}
+
+impl Status {
+    pub fn label(self) -> String {
+        match self {
+            Status::Found => "found".to_string(),
+            Status::Skipped => "skipped".to_string(),
+            Status::NotFound => "not found".to_string(),
+            Status::Error(failure) => format!("error: {}", failure.label()),
+        }
+    }
+
+    fn is_failure(self) -> bool {
+        matches!(self, Status::NotFound | Status::Error(_))
+    }
+
+    /// The status without the failure reason, as written to reports.
+    fn name(self) -> &'static str {
+        match self {
+            Status::Found => "found",
+            Status::Skipped => "skipped",
+            Status::NotFound => "not_found",
+            Status::Error(_) => "error",
+        }
+    }
+}
+
+/// Format of an exported report.
+#[derive(Clone, Copy, PartialEq)]
+pub enum ReportFormat {
+    Csv,
+    Json,
+}
+
+/// A row of an exported report.
+#[derive(Serialize)]
+struct ReportRow<'a> {
+    path: &'a Path,
+    artist: &'a str,
+    title: &'a str,
+    status: &'static str,
+    /// Provider of the written lyrics, empty for the other statuses.
+    provider: &'a str,
+    /// Reason of an error, empty for the other statuses.
+    error: &'a str,
+}
+
+pub struct FileResult {
+    pub file: PathBuf,
+    pub title: String,
+    pub artist: String,
+    pub status: Status,
+    /// Provider of written lyrics, or where kept lyrics are.
+    pub source: String,
+}
+
+#[derive(Default)]
+pub struct RunResults {
+    /// Title and artist of every file read so far.
+    tracks: Mutex<HashMap<PathBuf, (String, String)>>,
+    rows: Mutex<Vec<FileResult>>,
+    /// Index of each file's row in `rows`.
+    positions: Mutex<HashMap<PathBuf, usize>>,
+}
+
+impl RunResults {
+    pub fn clear(&self) {
+        self.tracks.lock().unwrap().clear();
+        self.positions.lock().unwrap().clear();
+        self.rows.lock().unwrap().clear();
+    }
+
+    pub fn add_track(&self, path: &Path, title: Option<&str>, artist: Option<&str>) {
+        let names = (title.unwrap_or_default().to_string(), artist.unwrap_or_default().to_string());
+        self.tracks.lock().unwrap().insert(path.to_path_buf(), names);
+    }
+
+    /// Adds the row of a manifest entry, replacing the file's row if it already has one, e.g.
+    /// after its lyrics were picked by hand.
+    pub fn record(&self, entry: &ManifestEntry) {
+        let (status, source) = match &entry.outcome {
+            Outcome::Written { provider, .. } => (Status::Found, provider.clone()),
+            Outcome::Kept { output } => (Status::Skipped, output.file_name().unwrap_or_default().to_string_lossy().into_owned()),
+            Outcome::Skipped => (Status::Skipped, String::new()),
+            Outcome::Failed { reason: Failure::NoMatch } => (Status::NotFound, String::new()),
+            Outcome::Failed { reason: Failure::Instrumental } => (Status::Skipped, "instrumental".to_string()),
+            Outcome::Failed { reason } => (Status::Error(*reason), String::new()),
+        };
+        let (title, artist) = self.tracks.lock().unwrap().get(&entry.file).cloned().unwrap_or_default();
+        let row = FileResult { file: entry.file.clone(), title, artist, status, source };
+        let mut positions = self.positions.lock().unwrap();
+        let mut rows = self.rows.lock().unwrap();
+        match positions.get(&entry.file) {
+            Some(&position) => rows[position] = row,
+            None => {
+                positions.insert(entry.file.clone(), rows.len());
+                rows.push(row);
+            }
+        }
+    }
+
+    /// The rows as a report for auditing and diffing runs, sorted by path so reports of the same
+    /// folder line up.
+    pub fn report(&self, format: ReportFormat) -> serde_json::Result<String> {
+        let rows = self.rows.lock().unwrap();
+        let errors: Vec<&str> = rows
+            .iter()
+            .map(|row| match row.status {
+                Status::Error(failure) => failure.label(),
+                _ => "",
+            })
+            .collect();
+        let mut report: Vec<ReportRow> = rows
+            .iter()
+            .zip(&errors)
+            .map(|(row, error)| ReportRow {
+                path: &row.file,
+                artist: &row.artist,
+                title: &row.title,
+                status: row.status.name(),
+                provider: if row.status == Status::Found { &row.source } else { "" },
+                error,
+            })
+            .collect();
+        report.sort_by(|a, b| a.path.cmp(b.path));
+        match format {
+            ReportFormat::Json => serde_json::to_string_pretty(&report),
+            ReportFormat::Csv => {
+                let mut csv = String::from("path,artist,title,status,provider,error\n");
+                for row in &report {
+                    let path = row.path.to_string_lossy();
+                    let fields = [&*path, row.artist, row.title, row.status, row.provider, row.error];
+                    csv.push_str(&fields.map(csv_field).join(","));
+                    csv.push('\n');
+                }
+                Ok(csv)
+            }
+        }
+    }
+}
+
+/// Quotes a CSV field if it contains a separator, quote or line break.
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+/// Width of the column holding each row's "Search manually" button.
+const ACTION_WIDTH: f32 = 24.0;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Column {
+    File,
+    Title,
+    Artist,
+    Status,
+    Source,
+}
+
+impl Column {
+    const ALL: [(Column, &'static str, f32); 5] = [
+        (Column::File, "File", 220.0),
+        (Column::Title, "Title", 160.0),
+        (Column::Artist, "Artist", 140.0),
+        (Column::Status, "Status", 150.0),
+        (Column::Source, "Source", 100.0),
+    ];
+
+    fn compare(self, a: &FileResult, b: &FileResult) -> Ordering {
+        match self {
+            Column::File => a.file.cmp(&b.file),
+            Column::Title => a.title.to_lowercase().cmp(&b.title.to_lowercase()),
+            Column::Artist => a.artist.to_lowercase().cmp(&b.artist.to_lowercase()),
+            Column::Status => a.status.cmp(&b.status),
+            Column::Source => a.source.cmp(&b.source),
+        }
+    }
+}
+
+/// Something to do with a single file, picked from its row.
+pub enum RowAction {
+    SearchManually(ManualSearch),
+    OpenFolder(PathBuf),
+    Refetch(PathBuf),
+    EditLrc(PathBuf),
+    DeleteLrc(PathBuf),
+    ViewEmbedded(PathBuf),
+}
+
+/// Sorting and filtering of the results table.
+#[derive(Default)]
+pub struct ResultsTable {
+    sort: Option<Column>,
+    descending: bool,
+    failures_only: bool,
+    /// File whose name was clicked, shown in the lyrics preview.
+    pub selected: Option<PathBuf>,
+}
+
+impl ResultsTable {
+    /// Shows the rows of `results`, in the order the files finished until a column is sorted by.
+    /// Right-clicking a file name opens a menu of actions on that file.
+    ///
+    /// Returns the action picked for a row, if any.
+    pub fn show(&mut self, ui: &mut egui::Ui, results: &RunResults) -> Option<RowAction> {
+        let rows = results.rows.lock().unwrap();
+        let failures = rows.iter().filter(|row| row.status.is_failure()).count();
+        ui.checkbox(&mut self.failures_only, format!("Show only files without lyrics ({} of {})", failures, rows.len()));
+        let mut shown: Vec<&FileResult> = rows.iter().filter(|row| !self.failures_only || row.status.is_failure()).collect();
+        if let Some(column) = self.sort {
+            shown.sort_by(|a, b| column.compare(a, b));
+            if self.descending {
+                shown.reverse();
+            }
+        }
+
+        let row_height = ui.text_style_height(&egui::TextStyle::Body);
+        ui.horizontal(|ui| {
+            cell(ui, ACTION_WIDTH, row_height, |_| {});
+            for (column, title, width) in Column::ALL {
+                let sorted = self.sort == Some(column);
+                let arrow = match (sorted, self.descending) {
+                    (false, _) => "",
+                    (true, false) => " ⏶",
+                    (true, true) => " ⏷",
+                };
+                cell(ui, width, row_height, |ui| {
+                    if ui.selectable_label(sorted, format!("{}{}", title, arrow)).on_hover_text("Sort").clicked() {
+                        self.descending = sorted && !self.descending;
+                        self.sort = Some(column);
+                    }
+                });
+            }
+        });
+        let mut action = None;
+        egui::ScrollArea::vertical().id_source("results").max_height(300.0).show_rows(ui, row_height, shown.len(), |ui, range| {
+            for row in &shown[range] {
+                ui.horizontal(|ui| {
+                    cell(ui, ACTION_WIDTH, row_height, |ui| {
+                        let button = ui.small_button("🔍").on_hover_text("Search manually");
+                        button.widget_info(|| egui::WidgetInfo::labeled(egui::WidgetType::Button, "Search manually"));
+                        if button.clicked() {
+                            action = Some(RowAction::SearchManually(ManualSearch::new(row.file.clone(), row.title.clone(), row.artist.clone())));
+                        }
+                    });
+                    let file = row.file.file_name().unwrap_or_default().to_string_lossy();
+                    let texts = [&*file, &row.title, &row.artist, &row.status.label(), &row.source];
+                    for (index, ((_, _, width), text)) in Column::ALL.into_iter().zip(texts).enumerate() {
+                        cell(ui, width, row_height, |ui| {
+                            if index == 0 {
+                                let selected = self.selected.as_ref() == Some(&row.file);
+                                let label = ui.selectable_label(selected, text).on_hover_text(row.file.display().to_string());
+                                if label.clicked() {
+                                    self.selected = (!selected).then(|| row.file.clone());
+                                }
+                                label.context_menu(|ui| {
+                                    if let Some(picked) = row_menu(ui, row) {
+                                        action = Some(picked);
+                                        ui.close_menu();
+                                    }
+                                });
+                            } else {
+                                ui.add(egui::Label::new(text).wrap(false));
+                            }
+                        });
+                    }
+                });
+            }
+        });
+        action
+    }
+}
+
+/// The actions offered for a row when its file name is right-clicked.
+fn row_menu(ui: &mut egui::Ui, row: &FileResult) -> Option<RowAction> {
+    let file = || row.file.clone();
+    let has_lrc = [true, false].iter().any(|&synced| output_path(&row.file, OutputMode::Lrc, synced).is_file());
+    if ui.button("Open containing folder").clicked() {
+        return Some(RowAction::OpenFolder(file()));
+    }
+    if ui.button("Fetch lyrics again").clicked() {
+        return Some(RowAction::Refetch(file()));
+    }
+    if ui.button("Search manually…").clicked() {
+        return Some(RowAction::SearchManually(ManualSearch::new(file(), row.title.clone(), row.artist.clone())));
+    }
+    if ui.add_enabled(has_lrc, egui::Button::new("Edit .lrc file")).clicked() {
+        return Some(RowAction::EditLrc(file()));
+    }
+    if ui.add_enabled(has_lrc, egui::Button::new("Delete .lrc file…")).clicked() {
+        return Some(RowAction::DeleteLrc(file()));
+    }
+    if ui.button("View embedded lyrics").clicked() {
+        return Some(RowAction::ViewEmbedded(file()));
+    }
+    None
+}
+
+/// Lays out a table cell of a fixed width, clipping what doesn't fit.
+fn cell(ui: &mut egui::Ui, width: f32, height: f32, add_contents: impl FnOnce(&mut egui::Ui)) {
+    ui.allocate_ui_with_layout(egui::vec2(width, height), egui::Layout::left_to_right(egui::Align::Center), |ui| {
+        ui.set_min_width(width);
+        ui.set_clip_rect(ui.max_rect().intersect(ui.clip_rect()));
+        add_contents(ui);
+    });
+}
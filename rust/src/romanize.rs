@@ -0,0 +1,125 @@
+//! Transliterating lyrics into Latin script, for car stereos and other players that can't show
+//! Japanese, Chinese, Korean or Cyrillic text.
+//!
+//! Kana become romaji, Chinese characters pinyin without tone marks, Hangul its revised
+//! romanization and Cyrillic the usual Latin spelling, as `any_ascii` spells them. Kanji in
+//! Japanese lyrics are read as Chinese, since their Japanese reading depends on the word and
+//! needs a dictionary. Everything else, such as accented Latin letters, timestamps and tags, is
+//! left as it is. Only lyrics in the languages chosen in `Languages` are romanized, so a Japanese
+//! line in English lyrics stays as it is.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::script::{is_cyrillic, is_han, is_hangul, Language};
+
+/// What is done with lyrics in the scripts above.
+#[derive(Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Romanization {
+    #[default]
+    Off,
+    /// Write and embed the transliterated lyrics instead of the original ones.
+    Replace,
+    /// Keep the original lyrics and also write the transliterated ones to a second file, see
+    /// `second_file_path`.
+    SecondFile,
+}
+
+impl Romanization {
+    pub const ALL: [Romanization; 3] = [Romanization::Off, Romanization::Replace, Romanization::SecondFile];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Romanization::Off => "Keep the original script",
+            Romanization::Replace => "Replace with the romanized text",
+            Romanization::SecondFile => "Also write a .romaji.lrc file",
+        }
+    }
+}
+
+/// Languages whose lyrics are romanized, as told by `script::language`.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Languages {
+    pub japanese: bool,
+    pub chinese: bool,
+    pub korean: bool,
+    /// Russian and Ukrainian.
+    pub cyrillic: bool,
+}
+
+impl Default for Languages {
+    fn default() -> Self {
+        Self { japanese: true, chinese: true, korean: true, cyrillic: true }
+    }
+}
+
+impl Languages {
+    /// Whether lyrics in `language` are romanized. Lyrics in other languages, or whose language
+    /// isn't recognized, are not.
+    pub fn includes(self, language: Option<Language>) -> bool {
+        match language {
+            Some(Language::Japanese) => self.japanese,
+            Some(Language::Chinese) => self.chinese,
+            Some(Language::Korean) => self.korean,
+            Some(Language::Russian | Language::Ukrainian) => self.cyrillic,
+            _ => false,
+        }
+    }
+}
+
+/// Whether `lyrics` have any text that `romanize` changes.
+pub fn needs_romanizing(lyrics: &str) -> bool {
+    lyrics.chars().any(is_romanized)
+}
+
+/// Transliterates the Japanese, Chinese, Korean and Cyrillic text of `lyrics`. Chinese characters
+/// are spelled in lowercase and set apart by spaces, one syllable each.
+pub fn romanize(lyrics: &str) -> String {
+    let mut romanized = String::with_capacity(lyrics.len());
+    let mut previous_han = false;
+    for c in lyrics.chars() {
+        if !is_romanized(c) {
+            if previous_han && c.is_alphanumeric() {
+                romanized.push(' ');
+            }
+            romanized.push(c);
+            previous_han = false;
+            continue;
+        }
+        let han = is_han(c);
+        let latin = match c {
+            // The long vowel mark of katakana lengthens the vowel before it
+            'ー' | 'ｰ' => romanized.chars().last().filter(|c| "aeiou".contains(*c)).map(String::from).unwrap_or_default(),
+            // any_ascii capitalizes every syllable
+            _ if han || is_hangul(c) => any_ascii::any_ascii_char(c).to_lowercase(),
+            _ => any_ascii::any_ascii_char(c).to_string(),
+        };
+        let starts_with_letter = latin.starts_with(|c: char| c.is_ascii_alphanumeric());
+        if (han || previous_han) && starts_with_letter && romanized.ends_with(|c: char| c.is_alphanumeric()) {
+            romanized.push(' ');
+        }
+        romanized.push_str(&latin);
+        previous_han = han;
+    }
+    romanized
+}
+
+/// Where the romanized copy of the lyrics file at `path` is written: `song.romaji.lrc` next to
+/// `song.lrc`, or `song.romaji.txt` next to `song.txt`.
+pub fn second_file_path(path: &Path) -> PathBuf {
+    let extension = path.extension().map(|ext| ext.to_string_lossy().into_owned()).unwrap_or_default();
+    path.with_extension(format!("romaji.{}", extension))
+}
+
+fn is_romanized(c: char) -> bool {
+    is_han(c)
+        || is_hangul(c)
+        || is_cyrillic(c)
+        || matches!(c,
+            '\u{3000}'..='\u{30FF}' // CJK punctuation, hiragana and katakana
+            | '\u{31F0}'..='\u{31FF}' // katakana extensions
+            | '\u{FF01}'..='\u{FF9F}' // full-width forms and half-width katakana
+        )
+}
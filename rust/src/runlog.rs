@@ -0,0 +1,81 @@
+//! Log files of runs, so the log survives closing the app.
+//!
+//! Every run writes its log to a file of its own in the log folder, named after the time it
+//! started. The log view stays the source: lines are copied to the file as they appear, each with
+//! the time it was written. Only the newest `KEPT_LOGS` files are kept.
+
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Number of run logs kept; older ones are deleted when a run starts.
+const KEPT_LOGS: usize = 20;
+
+/// The log file of the run in progress or the last one.
+pub struct RunLog {
+    pub path: PathBuf,
+    file: BufWriter<File>,
+    /// Lines of the log view already in the file.
+    written: usize,
+}
+
+impl RunLog {
+    /// Creates the log file of a run starting now in `dir`, deleting the oldest logs there.
+    pub fn start(dir: &Path) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let path = dir.join(format!("run-{}.log", chrono::Local::now().format("%Y-%m-%dT%H-%M-%S")));
+        let file = BufWriter::new(File::create(&path)?);
+        rotate(dir, &path);
+        Ok(Self { path, file, written: 0 })
+    }
+
+    /// Writes the lines of `logs` that aren't in the file yet. If the log view was cleared since,
+    /// e.g. to list the failures of a past run, its new lines are written as well.
+    pub fn append(&mut self, logs: &[String]) -> io::Result<()> {
+        if logs.len() < self.written {
+            self.written = 0;
+        }
+        if logs.len() == self.written {
+            return Ok(());
+        }
+        let time = chrono::Local::now().format("%H:%M:%S");
+        for line in &logs[self.written..] {
+            for line in line.trim_start_matches('\n').lines() {
+                writeln!(self.file, "{} {}", time, line)?;
+            }
+        }
+        self.written = logs.len();
+        self.file.flush()
+    }
+}
+
+/// Deletes all run logs in `dir` but the newest `KEPT_LOGS`, never `current`.
+fn rotate(dir: &Path, current: &Path) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    // The names sort by the time the runs started
+    let mut logs: Vec<PathBuf> = entries
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| path != current && path.file_name().is_some_and(|name| name.to_string_lossy().starts_with("run-")))
+        .collect();
+    logs.sort();
+    let excess = (logs.len() + 1).saturating_sub(KEPT_LOGS);
+    for old in &logs[..excess] {
+        let _ = fs::remove_file(old);
+    }
+}
+
+/// Opens `dir` in the system's file manager.
+pub fn open_folder(dir: &Path) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    let program = if cfg!(windows) {
+        "explorer"
+    } else if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    };
+    Command::new(program).arg(dir).spawn().map(drop)
+}
@@ -0,0 +1,198 @@
+//! Telling which writing system a title or artist is in, so queries can go to the providers
+//! covering that region first, and which language lyrics are in, so only some are romanized or
+//! translated.
+//!
+//! For titles, a single Chinese, Japanese or Korean character is enough, since Latin words such
+//! as "feat." or "Remix" are common in their titles but not the other way round. Lyrics are
+//! judged by the script most of their text is in. Japanese is told from Chinese by its kana,
+//! Ukrainian from Russian by its letters, and languages in Latin script by their most common
+//! words. Languages without such a list aren't recognized.
+
+use crate::lrc;
+
+/// A writing system, roughly standing for the region whose music uses it.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Script {
+    Latin,
+    Cyrillic,
+    /// Chinese characters, Japanese kana and Korean Hangul.
+    Cjk,
+}
+
+/// The script of `text`: CJK if it has any Chinese, Japanese or Korean character, else Cyrillic
+/// if it has any Cyrillic letter, else Latin if it has any letter. None if it has no letters.
+pub fn detect(text: &str) -> Option<Script> {
+    if text.chars().any(|c| is_han(c) || is_kana(c) || is_hangul(c)) {
+        Some(Script::Cjk)
+    } else if text.chars().any(is_cyrillic) {
+        Some(Script::Cyrillic)
+    } else if text.chars().any(char::is_alphabetic) {
+        Some(Script::Latin)
+    } else {
+        None
+    }
+}
+
+/// The script of a track from its `title`, or from its `artist` if the title has no letters.
+pub fn of_track(title: &str, artist: &str) -> Option<Script> {
+    detect(title).or_else(|| detect(artist))
+}
+
+/// A language lyrics can be recognized as.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Language {
+    Japanese,
+    Chinese,
+    Korean,
+    Russian,
+    Ukrainian,
+    English,
+    Spanish,
+    French,
+    German,
+    Portuguese,
+    Italian,
+}
+
+impl Language {
+    /// The ISO 639-1 code, as translation services name languages.
+    pub fn code(self) -> &'static str {
+        match self {
+            Language::Japanese => "ja",
+            Language::Chinese => "zh",
+            Language::Korean => "ko",
+            Language::Russian => "ru",
+            Language::Ukrainian => "uk",
+            Language::English => "en",
+            Language::Spanish => "es",
+            Language::French => "fr",
+            Language::German => "de",
+            Language::Portuguese => "pt",
+            Language::Italian => "it",
+        }
+    }
+}
+
+/// Common short words of the languages recognized in Latin script.
+const COMMON_WORDS: [(Language, [&str; 16]); 6] = [
+    (Language::English, ["the", "and", "you", "i", "me", "my", "is", "it", "to", "your", "that", "with", "we", "are", "this", "don't"]),
+    (Language::Spanish, ["el", "que", "y", "mi", "tu", "yo", "es", "por", "con", "los", "las", "una", "del", "pero", "como", "quiero"]),
+    (Language::French, ["le", "les", "et", "je", "tu", "pas", "est", "une", "des", "dans", "pour", "mon", "moi", "qui", "nous", "c'est"]),
+    (Language::German, ["der", "die", "das", "und", "ich", "du", "nicht", "ist", "ein", "eine", "mit", "mich", "dich", "wir", "auf", "mein"]),
+    (Language::Portuguese, ["o", "e", "eu", "não", "você", "um", "uma", "meu", "minha", "com", "em", "do", "da", "os", "mais", "é"]),
+    (Language::Italian, ["il", "che", "di", "non", "io", "un", "mi", "ti", "per", "sono", "è", "nel", "ma", "ho", "della", "perché"]),
+];
+
+/// The language most of the text of `lyrics` is in, ignoring timestamps and tags. None if it has
+/// no letters or isn't in one of the recognized languages.
+pub fn language(lyrics: &str) -> Option<Language> {
+    let lines: Vec<&str> = lyrics.lines().map(lrc::strip_timestamps).filter(|line| !line.starts_with('[')).collect();
+    let chars = || lines.iter().flat_map(|line| line.chars());
+    let count = |class: fn(char) -> bool| chars().filter(|&c| class(c)).count();
+    let (han, kana, hangul) = (count(is_han), count(is_kana), count(is_hangul));
+    let cyrillic = count(is_cyrillic);
+    let latin = chars().filter(|c| c.is_alphabetic() && !is_han(*c) && !is_kana(*c) && !is_hangul(*c) && !is_cyrillic(*c)).count();
+    // A CJK character stands for a syllable or a word, a few letters in the other scripts
+    let cjk = 3 * (han + kana + hangul);
+    if cjk == 0 && cyrillic == 0 && latin == 0 {
+        return None;
+    }
+    if cjk >= cyrillic && cjk >= latin {
+        return Some(if hangul > han + kana {
+            Language::Korean
+        } else if 10 * kana >= han + kana {
+            Language::Japanese
+        } else {
+            Language::Chinese
+        });
+    }
+    if cyrillic >= latin {
+        let ukrainian = chars().any(|c| matches!(c, 'і' | 'ї' | 'є' | 'ґ' | 'І' | 'Ї' | 'Є' | 'Ґ'));
+        return Some(if ukrainian { Language::Ukrainian } else { Language::Russian });
+    }
+    let words: Vec<String> = lines
+        .iter()
+        .flat_map(|line| line.split(|c: char| !c.is_alphabetic() && c != '\'' && c != '’'))
+        .map(|word| word.replace('’', "'").to_lowercase())
+        .collect();
+    COMMON_WORDS
+        .iter()
+        .map(|(language, common)| (*language, words.iter().filter(|word| common.contains(&word.as_str())).count()))
+        .filter(|&(_, hits)| hits > 0)
+        .max_by_key(|&(_, hits)| hits)
+        .map(|(language, _)| language)
+}
+
+pub fn is_han(c: char) -> bool {
+    matches!(c, '\u{3400}'..='\u{4DBF}' | '\u{4E00}'..='\u{9FFF}' | '\u{F900}'..='\u{FAFF}')
+}
+
+/// Hiragana, katakana and their extensions, including half-width katakana.
+pub fn is_kana(c: char) -> bool {
+    matches!(c, '\u{3041}'..='\u{30FF}' | '\u{31F0}'..='\u{31FF}' | '\u{FF66}'..='\u{FF9F}')
+}
+
+pub fn is_hangul(c: char) -> bool {
+    matches!(c, '\u{1100}'..='\u{11FF}' | '\u{3130}'..='\u{318F}' | '\u{AC00}'..='\u{D7AF}')
+}
+
+pub fn is_cyrillic(c: char) -> bool {
+    matches!(c, '\u{0400}'..='\u{052F}')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_single_cjk_character_makes_a_title_cjk() {
+        assert_eq!(detect("紅蓮華 (TV Size)"), Some(Script::Cjk));
+        assert_eq!(detect("Dynamite (방탄소년단)"), Some(Script::Cjk));
+        assert_eq!(detect("ｱｲｳ"), Some(Script::Cjk));
+    }
+
+    #[test]
+    fn cyrillic_and_latin_titles() {
+        assert_eq!(detect("Кукла колдуна"), Some(Script::Cyrillic));
+        assert_eq!(detect("Café del Mar"), Some(Script::Latin));
+    }
+
+    #[test]
+    fn japanese_is_told_from_chinese_by_its_kana() {
+        assert_eq!(language("[00:01.00]君の名前を呼んでいた\n[00:05.00]遠い空の下で\n"), Some(Language::Japanese));
+        assert_eq!(language("[00:01.00]故事的小黄花\n[00:05.00]从出生那年就飘着\n"), Some(Language::Chinese));
+        assert_eq!(language("[00:01.00]사랑해 너를\n"), Some(Language::Korean));
+    }
+
+    #[test]
+    fn translation_lines_dont_outweigh_the_original() {
+        let lyrics = "[00:01.00]君の名前を呼んでいた\n[00:01.00]I was calling your name\n";
+        assert_eq!(language(lyrics), Some(Language::Japanese));
+    }
+
+    #[test]
+    fn ukrainian_is_told_from_russian_by_its_letters() {
+        assert_eq!(language("Я тебе кохаю, моя зоре"), Some(Language::Russian));
+        assert_eq!(language("Ніч яка місячна, зоряна, ясная"), Some(Language::Ukrainian));
+    }
+
+    #[test]
+    fn latin_languages_are_told_apart_by_common_words() {
+        assert_eq!(language("[ar:Artist]\n[00:01.00]I don't know what to do with you\n"), Some(Language::English));
+        assert_eq!(language("[00:01.00]Ich weiß nicht, was du mit mir machst\n"), Some(Language::German));
+        assert_eq!(language("[00:01.00]Yo no sé lo que quiero con los dos\n"), Some(Language::Spanish));
+    }
+
+    #[test]
+    fn text_without_letters_or_known_words_has_no_language() {
+        assert_eq!(language("[00:01.00]♪\n[00:02.00]...\n"), None);
+        assert_eq!(language("Lorem ipsum dolor sit amet"), None);
+    }
+
+    #[test]
+    fn title_without_letters_falls_back_to_the_artist() {
+        assert_eq!(detect("1979"), None);
+        assert_eq!(of_track("1979", "周杰伦"), Some(Script::Cjk));
+        assert_eq!(of_track("晴天", "Jay Chou"), Some(Script::Cjk));
+    }
+}
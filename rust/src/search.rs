@@ -0,0 +1,95 @@
+//! Full-text search over the lyrics the app has written.
+//!
+//! Every file whose lyrics are written, embedded or kept during a run is added to a `tantivy`
+//! index in the cache directory, so the GUI can answer "which of my songs contains this line?".
+//! The index only holds copies of lyrics that live in the library, so it can be deleted at any time.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use tantivy::collector::TopDocs;
+use tantivy::directory::MmapDirectory;
+use tantivy::query::QueryParser;
+use tantivy::schema::{Field, Schema, Value, STORED, STRING, TEXT};
+use tantivy::{doc, Index, IndexReader, IndexWriter, TantivyDocument, Term};
+
+/// Memory the index writer may use before flushing to disk, in bytes.
+const WRITER_MEMORY: usize = 50_000_000;
+
+/// A file whose lyrics match a search.
+#[derive(Clone)]
+pub struct SearchHit {
+    pub file: PathBuf,
+    pub title: String,
+    pub artist: String,
+    /// The first lyric line containing one of the searched words.
+    pub line: Option<String>,
+}
+
+pub struct LyricsIndex {
+    index: Index,
+    writer: Mutex<IndexWriter>,
+    reader: IndexReader,
+    file: Field,
+    title: Field,
+    artist: Field,
+    lyrics: Field,
+}
+
+impl LyricsIndex {
+    /// Opens the index in `dir`, creating it if needed.
+    pub fn open(dir: &Path) -> tantivy::Result<Self> {
+        let mut schema = Schema::builder();
+        let file = schema.add_text_field("file", STRING | STORED);
+        let title = schema.add_text_field("title", TEXT | STORED);
+        let artist = schema.add_text_field("artist", TEXT | STORED);
+        let lyrics = schema.add_text_field("lyrics", TEXT | STORED);
+        fs::create_dir_all(dir)?;
+        let index = Index::open_or_create(MmapDirectory::open(dir)?, schema.build())?;
+        let writer = Mutex::new(index.writer(WRITER_MEMORY)?);
+        let reader = index.reader()?;
+        Ok(Self { index, writer, reader, file, title, artist, lyrics })
+    }
+
+    /// Adds or replaces the lyrics of `file`. They become searchable after the next `commit`.
+    pub fn add(&self, file: &Path, title: &str, artist: &str, lyrics: &str) -> tantivy::Result<()> {
+        let path = file.to_string_lossy();
+        let writer = self.writer.lock().unwrap();
+        writer.delete_term(Term::from_field_text(self.file, &path));
+        writer.add_document(doc!(
+            self.file => path.as_ref(),
+            self.title => title,
+            self.artist => artist,
+            self.lyrics => lyrics,
+        ))?;
+        Ok(())
+    }
+
+    pub fn commit(&self) -> tantivy::Result<()> {
+        self.writer.lock().unwrap().commit()?;
+        self.reader.reload()
+    }
+
+    /// Finds up to `limit` files whose lyrics, title or artist contain all words of `query`.
+    pub fn search(&self, query: &str, limit: usize) -> tantivy::Result<Vec<SearchHit>> {
+        let mut parser = QueryParser::for_index(&self.index, vec![self.lyrics, self.title, self.artist]);
+        parser.set_conjunction_by_default();
+        let (parsed, _) = parser.parse_query_lenient(query);
+        let searcher = self.reader.searcher();
+        let words: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+        let mut hits = Vec::new();
+        for (_, address) in searcher.search(&parsed, &TopDocs::with_limit(limit).order_by_score())? {
+            let doc: TantivyDocument = searcher.doc(address)?;
+            let text = |field| doc.get_first(field).and_then(|value| value.as_str()).unwrap_or_default().to_string();
+            let lyrics = text(self.lyrics);
+            let line = lyrics
+                .lines()
+                .map(crate::lrc::strip_timestamps)
+                .find(|line| words.iter().any(|word| line.to_lowercase().contains(word)))
+                .map(str::to_string);
+            hits.push(SearchHit { file: PathBuf::from(text(self.file)), title: text(self.title), artist: text(self.artist), line });
+        }
+        Ok(hits)
+    }
+}
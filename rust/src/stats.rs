@@ -0,0 +1,100 @@
+//! Statistics of a library, gathered without looking anything up.
+//!
+//! "Scan only" reads the tags of every file a run would process and counts, by format, which
+//! already have lyrics in a `.lrc` file or in their tags and which lack the title or artist a
+//! lookup needs. What is left is what a run would spend requests on.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use eframe::egui;
+
+/// Counts of the tracks of one format, or of all of them.
+#[derive(Clone, Copy, Default)]
+pub struct FormatStats {
+    pub tracks: usize,
+    /// Tracks with a `.lrc` or `.txt` file next to them.
+    pub lrc: usize,
+    /// Tracks with lyrics in their tags.
+    pub embedded: usize,
+    /// Tracks without a title or artist, even from a sidecar or the file name.
+    pub no_metadata: usize,
+    /// Tracks without lyrics of either kind that can be looked up.
+    pub to_look_up: usize,
+}
+
+impl FormatStats {
+    fn add(&mut self, other: &FormatStats) {
+        self.tracks += other.tracks;
+        self.lrc += other.lrc;
+        self.embedded += other.embedded;
+        self.no_metadata += other.no_metadata;
+        self.to_look_up += other.to_look_up;
+    }
+}
+
+/// What the scan found out about a single track.
+pub struct TrackFacts {
+    pub ext: String,
+    pub has_lrc: bool,
+    pub has_embedded: bool,
+    pub has_metadata: bool,
+}
+
+pub struct LibraryStats {
+    pub folder: PathBuf,
+    by_format: BTreeMap<String, FormatStats>,
+    /// Cleared if the scan was cancelled before it got through the library.
+    pub complete: bool,
+}
+
+impl LibraryStats {
+    pub fn new(folder: PathBuf) -> Self {
+        Self { folder, by_format: BTreeMap::new(), complete: true }
+    }
+
+    pub fn add(&mut self, track: &TrackFacts) {
+        let stats = self.by_format.entry(track.ext.clone()).or_default();
+        let count = |fact: bool| usize::from(fact);
+        stats.add(&FormatStats {
+            tracks: 1,
+            lrc: count(track.has_lrc),
+            embedded: count(track.has_embedded),
+            no_metadata: count(!track.has_metadata),
+            to_look_up: count(track.has_metadata && !track.has_lrc && !track.has_embedded),
+        });
+    }
+
+    pub fn total(&self) -> FormatStats {
+        let mut total = FormatStats::default();
+        for stats in self.by_format.values() {
+            total.add(stats);
+        }
+        total
+    }
+
+    /// Shows the counts as a table, a row for each format and one for all of them.
+    pub fn show(&self, ui: &mut egui::Ui) {
+        ui.label(self.folder.display().to_string());
+        if !self.complete {
+            ui.label("⚠ The scan was cancelled, so these are the files scanned until then.");
+        }
+        egui::Grid::new("library_stats").striped(true).num_columns(6).show(ui, |ui| {
+            for heading in ["Format", "Tracks", "With .lrc", "Embedded", "No metadata", "To look up"] {
+                ui.strong(heading);
+            }
+            ui.end_row();
+            let row = |ui: &mut egui::Ui, format: &str, stats: &FormatStats| {
+                ui.label(format);
+                for count in [stats.tracks, stats.lrc, stats.embedded, stats.no_metadata, stats.to_look_up] {
+                    ui.label(count.to_string());
+                }
+                ui.end_row();
+            };
+            for (ext, stats) in &self.by_format {
+                row(ui, ext, stats);
+            }
+            row(ui, "All", &self.total());
+        });
+    }
+}
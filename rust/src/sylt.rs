@@ -0,0 +1,183 @@
+//! The frames ID3v2 tags store lyrics in.
+//!
+//! USLT frames hold plain text, which older tools (and the embed mode) fill with LRC lyrics,
+//! timestamps included. SYLT frames store every line with its time as binary data, which some
+//! players need to show synced lyrics. lofty's generic `Tag` drops SYLT frames and the language
+//! and description of USLT frames, so this module works on the file's `Id3v2Tag` directly and
+//! leaves all other frames alone.
+
+use std::fs::File;
+use std::path::Path;
+
+use lofty::id3::v2::{
+    Frame, FrameFlags, FrameValue, Id3v2Tag, SyncTextContentType, SynchronizedText, TimestampFormat,
+    UnsynchronizedTextFrame,
+};
+use lofty::iff::aiff::AiffFile;
+use lofty::iff::wav::WavFile;
+use lofty::mpeg::MpegFile;
+use lofty::{AudioFile, FileType, ParseOptions, TagExt, TextEncoding};
+use serde::{Deserialize, Serialize};
+
+use crate::config::LyricsFrame;
+use crate::lrc;
+
+const USLT: &str = "USLT";
+const SYLT: &str = "SYLT";
+
+/// The frame lyrics are converted into.
+#[derive(Clone, Copy)]
+pub enum Target {
+    Sylt,
+    Uslt,
+}
+
+/// Reads the lyrics that would be converted into `target`, as LRC text.
+///
+/// For SYLT these are the first USLT lyrics with timestamps, for USLT the first SYLT lyrics
+/// timed in milliseconds. Returns None if the file has no such lyrics.
+pub fn read_source(path: &Path, target: Target) -> lofty::Result<Option<String>> {
+    let tag = read_tag(path)?;
+    Ok(match target {
+        Target::Sylt => tag.unsync_text().map(|frame| &frame.content).find(|content| lrc::is_synced(content)).cloned(),
+        Target::Uslt => synced_lyrics(&tag).map(|sync| lrc::format_timed_lines(&sync.content)),
+    })
+}
+
+/// Replaces the file's USLT and SYLT frames with a single `target` frame holding `lyrics`.
+///
+/// The new frame keeps the encoding, language and description of the frame it replaces.
+pub fn write(path: &Path, lyrics: &str, target: Target) -> lofty::Result<()> {
+    let mut tag = read_tag(path)?;
+    let (encoding, language, description) = match (tag.unsync_text().next(), synced_lyrics(&tag)) {
+        (Some(frame), _) => (frame.encoding, frame.language, frame.description.clone()),
+        (None, Some(sync)) => (sync.encoding, sync.language, sync.description.unwrap_or_default()),
+        (None, None) => (TextEncoding::UTF8, *b"XXX", String::new()),
+    };
+    tag.retain(|frame| frame.id_str() != USLT && frame.id_str() != SYLT);
+    let value = match target {
+        Target::Sylt => {
+            let sync = SynchronizedText {
+                encoding,
+                language,
+                timestamp_format: TimestampFormat::MS,
+                content_type: SyncTextContentType::Lyrics,
+                description: Some(description).filter(|description| !description.is_empty()),
+                content: lrc::timed_lines(lyrics),
+            };
+            FrameValue::Binary(sync.as_bytes()?)
+        }
+        Target::Uslt => FrameValue::UnsynchronizedText(UnsynchronizedTextFrame {
+            encoding,
+            language,
+            description,
+            content: lyrics.to_string(),
+        }),
+    };
+    let id = match target {
+        Target::Sylt => SYLT,
+        Target::Uslt => USLT,
+    };
+    tag.insert(Frame::new(id, value, FrameFlags::default())?);
+    tag.save_to_path(path)
+}
+
+/// Writes the ID3v2 lyrics frames the generic tag can't: one USLT frame per entry of `frames`,
+/// each holding `lyrics`, and with `sylt` set a SYLT frame timed like `lyrics`.
+///
+/// USLT frames are only replaced if `frames` isn't empty, and the SYLT frame only if `lyrics`
+/// are synced. The SYLT frame takes the language of the first entry of `frames`.
+pub fn write_frames(path: &Path, lyrics: &str, frames: &[LyricsFrame], sylt: bool) -> lofty::Result<()> {
+    let mut tag = read_tag(path)?;
+    if !frames.is_empty() {
+        tag.retain(|frame| frame.id_str() != USLT);
+        for frame in frames {
+            let value = FrameValue::UnsynchronizedText(UnsynchronizedTextFrame {
+                encoding: TextEncoding::UTF8,
+                language: frame.language_code(),
+                description: frame.description.clone(),
+                content: lyrics.to_string(),
+            });
+            tag.insert(Frame::new(USLT, value, FrameFlags::default())?);
+        }
+    }
+    if sylt && lrc::is_synced(lyrics) {
+        tag.retain(|frame| frame.id_str() != SYLT);
+        let sync = SynchronizedText {
+            encoding: TextEncoding::UTF8,
+            language: frames.first().map_or(*b"XXX", LyricsFrame::language_code),
+            timestamp_format: TimestampFormat::MS,
+            content_type: SyncTextContentType::Lyrics,
+            description: None,
+            content: lrc::timed_lines(lyrics),
+        };
+        tag.insert(Frame::new(SYLT, FrameValue::Binary(sync.as_bytes()?), FrameFlags::default())?);
+    }
+    tag.save_to_path(path)
+}
+
+/// A USLT or SYLT frame as it was stored, kept to be put back by `restore_frames`.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "frame", rename_all = "snake_case")]
+pub enum SavedFrame {
+    Uslt { language: String, description: String, content: String },
+    /// The frame's content as stored, as lofty doesn't parse SYLT frames.
+    Sylt { data: Vec<u8> },
+}
+
+/// The USLT and SYLT frames of the file's ID3v2 tag.
+pub fn lyrics_frames(path: &Path) -> lofty::Result<Vec<SavedFrame>> {
+    let tag = read_tag(path)?;
+    let frames = tag.into_iter().filter_map(|frame| match frame.content() {
+        FrameValue::UnsynchronizedText(uslt) if frame.id_str() == USLT => Some(SavedFrame::Uslt {
+            language: String::from_utf8_lossy(&uslt.language).into_owned(),
+            description: uslt.description.clone(),
+            content: uslt.content.clone(),
+        }),
+        FrameValue::Binary(data) if frame.id_str() == SYLT => Some(SavedFrame::Sylt { data: data.clone() }),
+        _ => None,
+    });
+    Ok(frames.collect())
+}
+
+/// Replaces the file's USLT and SYLT frames with `frames`, as read by `lyrics_frames`.
+pub fn restore_frames(path: &Path, frames: &[SavedFrame]) -> lofty::Result<()> {
+    let mut tag = read_tag(path)?;
+    tag.retain(|frame| frame.id_str() != USLT && frame.id_str() != SYLT);
+    for frame in frames {
+        let frame = match frame {
+            SavedFrame::Uslt { language, description, content } => {
+                let language = LyricsFrame { language: language.clone(), description: String::new() }.language_code();
+                let value = UnsynchronizedTextFrame { encoding: TextEncoding::UTF8, language, description: description.clone(), content: content.clone() };
+                Frame::new(USLT, FrameValue::UnsynchronizedText(value), FrameFlags::default())?
+            }
+            SavedFrame::Sylt { data } => Frame::new(SYLT, FrameValue::Binary(data.clone()), FrameFlags::default())?,
+        };
+        tag.insert(frame);
+    }
+    tag.save_to_path(path)
+}
+
+/// Reads the ID3v2 tag of an MP3 file, or of the `ID3 ` chunk of a WAV or AIFF file, or an
+/// empty tag if it has none.
+fn read_tag(path: &Path) -> lofty::Result<Id3v2Tag> {
+    let mut file = File::open(path)?;
+    let tag = match FileType::from_path(path) {
+        Some(FileType::Wav) => WavFile::read_from(&mut file, ParseOptions::new())?.id3v2().cloned(),
+        Some(FileType::Aiff) => AiffFile::read_from(&mut file, ParseOptions::new())?.id3v2().cloned(),
+        _ => MpegFile::read_from(&mut file, ParseOptions::new())?.id3v2().cloned(),
+    };
+    Ok(tag.unwrap_or_default())
+}
+
+/// The first SYLT frame of `tag` that holds lyrics timed in milliseconds.
+///
+/// Frames timed in MPEG frames can't be converted without decoding the audio and are ignored.
+fn synced_lyrics(tag: &Id3v2Tag) -> Option<SynchronizedText> {
+    tag.into_iter().filter(|frame| frame.id_str() == SYLT).find_map(|frame| match frame.content() {
+        FrameValue::Binary(data) => SynchronizedText::parse(data)
+            .ok()
+            .filter(|sync| sync.content_type == SyncTextContentType::Lyrics && sync.timestamp_format == TimestampFormat::MS),
+        _ => None,
+    })
+}
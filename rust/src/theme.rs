@@ -0,0 +1,90 @@
+//! Colors of the GUI, loaded from the `theme` section of the config file.
+//!
+//! Colors are written as `#rrggbb` hex strings. Unset colors keep egui's defaults for the light or
+//! dark base theme, and log lines without a color for their status use the normal text color.
+
+use std::collections::BTreeMap;
+
+use eframe::egui::{Color32, Visuals};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    pub dark: bool,
+    /// Use the light or dark base theme of the operating system instead of `dark`.
+    pub follow_system: bool,
+    /// Color of selections, links and the focused widget.
+    pub accent: Option<String>,
+    /// Fill of the main panel and windows.
+    pub background: Option<String>,
+    pub log_colors: BTreeMap<LogStatus, String>,
+}
+
+/// Kind of a log line, recognized by its prefix.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogStatus {
+    Success,
+    Error,
+    Warning,
+    Info,
+    Debug,
+}
+
+impl LogStatus {
+    fn of(line: &str) -> Option<Self> {
+        let line = line.trim_start();
+        if line.starts_with(['✔', '✅', '💾']) {
+            Some(LogStatus::Success)
+        } else if line.starts_with(['❌', '✘']) {
+            Some(LogStatus::Error)
+        } else if line.starts_with(['⚠', '⏱', '⏸']) {
+            Some(LogStatus::Warning)
+        } else if line.starts_with("[INFO]") {
+            Some(LogStatus::Info)
+        } else if line.starts_with("[DEBUG]") {
+            Some(LogStatus::Debug)
+        } else {
+            None
+        }
+    }
+}
+
+impl Theme {
+    /// The visuals of this theme. `system_dark` tells whether the operating system uses a dark
+    /// theme, if known; when it isn't, following the system falls back to the light theme.
+    pub fn visuals(&self, system_dark: Option<bool>) -> Visuals {
+        let dark = match self.follow_system {
+            true => system_dark.unwrap_or(false),
+            false => self.dark,
+        };
+        let mut visuals = if dark { Visuals::dark() } else { Visuals::light() };
+        if let Some(accent) = self.accent.as_deref().and_then(parse_color) {
+            visuals.selection.bg_fill = accent;
+            visuals.hyperlink_color = accent;
+            visuals.widgets.hovered.bg_stroke.color = accent;
+            visuals.widgets.active.bg_fill = accent;
+        }
+        if let Some(background) = self.background.as_deref().and_then(parse_color) {
+            visuals.panel_fill = background;
+            visuals.window_fill = background;
+        }
+        visuals
+    }
+
+    /// The color configured for the status of a log line, if any.
+    pub fn log_color(&self, line: &str) -> Option<Color32> {
+        self.log_colors.get(&LogStatus::of(line)?).and_then(|color| parse_color(color))
+    }
+}
+
+/// Parses a `#rrggbb` color.
+fn parse_color(hex: &str) -> Option<Color32> {
+    let hex = hex.trim().strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+    let channel = |range| u8::from_str_radix(hex.get(range)?, 16).ok();
+    Some(Color32::from_rgb(channel(0..2)?, channel(2..4)?, channel(4..6)?))
+}
@@ -0,0 +1,219 @@
+//! Translations shown beneath the original lyrics.
+//!
+//! Many players show an LRC line and the line with the same timestamp after it together, which
+//! is how dual-language lyrics are written: every line is followed by its translation. Lines are
+//! sent to a translation service in a single request per track. LibreTranslate (self-hosted or
+//! a public instance) and DeepL are supported; further services implement `Translator`.
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::lrc;
+use crate::network::{http_client, runtime};
+use crate::script::Language;
+
+#[derive(Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Backend {
+    #[default]
+    LibreTranslate,
+    #[serde(rename = "deepl")]
+    DeepL,
+}
+
+impl Backend {
+    pub const ALL: [Backend; 2] = [Backend::LibreTranslate, Backend::DeepL];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Backend::LibreTranslate => "LibreTranslate",
+            Backend::DeepL => "DeepL",
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct TranslationConfig {
+    pub enabled: bool,
+    pub backend: Backend,
+    /// Address of the LibreTranslate server.
+    pub url: String,
+    /// Needed by DeepL and by most public LibreTranslate servers.
+    pub api_key: String,
+    /// Language code to translate into, such as `en` or `de`.
+    pub target_language: String,
+    /// Comma-separated codes of the languages whose lyrics are translated, such as `ja, ko`. Empty
+    /// for lyrics in any language.
+    #[serde(default)]
+    pub from_languages: String,
+}
+
+impl Default for TranslationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            backend: Backend::LibreTranslate,
+            url: "https://libretranslate.com".to_string(),
+            api_key: String::new(),
+            target_language: "en".to_string(),
+            from_languages: String::new(),
+        }
+    }
+}
+
+impl TranslationConfig {
+    /// Whether lyrics in `language`, as told by `script::language`, are translated. Lyrics already
+    /// in the target language never are, and with `from_languages` set, only lyrics in one of them
+    /// are.
+    pub fn translates(&self, language: Option<Language>) -> bool {
+        let target = self.target_language.trim().to_lowercase();
+        let base = target.split(['-', '_']).next().unwrap_or_default();
+        if language.is_some_and(|language| language.code() == base) {
+            return false;
+        }
+        let mut from = self.from_languages.split(',').map(|code| code.trim().to_lowercase()).filter(|code| !code.is_empty()).peekable();
+        from.peek().is_none() || language.is_some_and(|language| from.any(|code| code == language.code()))
+    }
+
+    /// The service to translate with, or None if translations are off.
+    pub fn translator(&self) -> Option<Box<dyn Translator>> {
+        if !self.enabled || self.target_language.trim().is_empty() {
+            return None;
+        }
+        let api_key = self.api_key.trim().to_string();
+        Some(match self.backend {
+            Backend::LibreTranslate => Box::new(LibreTranslate { url: self.url.trim().trim_end_matches('/').to_string(), api_key }),
+            Backend::DeepL => Box::new(DeepL { api_key }),
+        })
+    }
+}
+
+/// A translation service.
+pub trait Translator {
+    /// Translates each of `texts` into `target`, returning the translations in the same order.
+    fn translate(&self, texts: &[&str], target: &str) -> Result<Vec<String>, String>;
+}
+
+/// Adds the translation of every line of `lyrics` into `target` beneath it, with the same
+/// timestamps. Tags, empty lines and lines the service returns unchanged, such as ones already in
+/// the target language, get no translation.
+pub fn with_translations(lyrics: &str, translator: &dyn Translator, target: &str) -> Result<String, String> {
+    let lines: Vec<(&str, &str)> = lyrics
+        .lines()
+        .map(|line| {
+            let text = lrc::strip_timestamps(line);
+            (&line[..line.len() - text.len()], text.trim())
+        })
+        .collect();
+    let translatable = |text: &str| !text.is_empty() && !text.starts_with('[');
+    let texts: Vec<&str> = lines.iter().map(|&(_, text)| text).filter(|text| translatable(text)).collect();
+    if texts.is_empty() {
+        return Ok(lyrics.to_string());
+    }
+    let translations = translator.translate(&texts, target.trim())?;
+    if translations.len() != texts.len() {
+        return Err(format!("got {} translations for {} lines", translations.len(), texts.len()));
+    }
+    let mut translations = translations.into_iter();
+    let mut translated = String::new();
+    for (stamps, text) in lines {
+        translated.push_str(stamps);
+        translated.push_str(text);
+        translated.push('\n');
+        if !translatable(text) {
+            continue;
+        }
+        if let Some(translation) = translations.next().filter(|translation| translation.trim() != text) {
+            translated.push_str(stamps);
+            translated.push_str(translation.trim());
+            translated.push('\n');
+        }
+    }
+    Ok(translated)
+}
+
+/// A LibreTranslate server, see https://libretranslate.com/docs.
+struct LibreTranslate {
+    url: String,
+    api_key: String,
+}
+
+#[derive(Deserialize)]
+struct LibreTranslateResponse {
+    #[serde(rename = "translatedText")]
+    translated_text: Vec<String>,
+}
+
+impl Translator for LibreTranslate {
+    fn translate(&self, texts: &[&str], target: &str) -> Result<Vec<String>, String> {
+        let mut body = json!({ "q": texts, "source": "auto", "target": target.to_lowercase(), "format": "text" });
+        if !self.api_key.is_empty() {
+            body["api_key"] = json!(self.api_key);
+        }
+        let request = http_client().post(format!("{}/translate", self.url)).json(&body);
+        let response: LibreTranslateResponse = send(request)?;
+        Ok(response.translated_text)
+    }
+}
+
+/// The DeepL API, see https://developers.deepl.com/docs.
+struct DeepL {
+    api_key: String,
+}
+
+#[derive(Deserialize)]
+struct DeepLResponse {
+    translations: Vec<DeepLTranslation>,
+}
+
+#[derive(Deserialize)]
+struct DeepLTranslation {
+    text: String,
+}
+
+impl Translator for DeepL {
+    fn translate(&self, texts: &[&str], target: &str) -> Result<Vec<String>, String> {
+        if self.api_key.is_empty() {
+            return Err("DeepL needs an API key".to_string());
+        }
+        // Keys of the free plan only work with the free API
+        let host = if self.api_key.ends_with(":fx") { "api-free.deepl.com" } else { "api.deepl.com" };
+        let request = http_client()
+            .post(format!("https://{}/v2/translate", host))
+            .header("Authorization", format!("DeepL-Auth-Key {}", self.api_key))
+            .json(&json!({ "text": texts, "target_lang": target.to_uppercase() }));
+        let response: DeepLResponse = send(request)?;
+        Ok(response.translations.into_iter().map(|translation| translation.text).collect())
+    }
+}
+
+fn send<T: serde::de::DeserializeOwned>(request: reqwest::RequestBuilder) -> Result<T, String> {
+    runtime().block_on(async {
+        let response = request.send().await.map_err(|e| e.to_string())?;
+        match response.status() {
+            status if status.is_success() => response.json().await.map_err(|e| e.to_string()),
+            status => Err(format!("the service answered {}: {}", status, response.text().await.unwrap_or_default())),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lyrics_in_the_target_language_are_not_translated() {
+        let config = TranslationConfig { target_language: "EN-GB".to_string(), ..TranslationConfig::default() };
+        assert!(!config.translates(Some(Language::English)));
+        assert!(config.translates(Some(Language::Japanese)));
+        assert!(config.translates(None));
+    }
+
+    #[test]
+    fn only_the_listed_languages_are_translated() {
+        let config = TranslationConfig { from_languages: "ja, KO".to_string(), ..TranslationConfig::default() };
+        assert!(config.translates(Some(Language::Korean)));
+        assert!(!config.translates(Some(Language::Chinese)));
+        assert!(!config.translates(None));
+    }
+}
@@ -0,0 +1,116 @@
+//! An icon in the system tray to hide the window to, so a long run or a watched folder keeps
+//! going out of the way.
+//!
+//! Only built with the `tray` feature, as the icon needs GTK and libappindicator on Linux. There
+//! the icon lives on a thread of its own running GTK's main loop, since eframe doesn't use GTK;
+//! elsewhere it is created on the UI thread, whose event loop it needs. Clicks and menu picks are
+//! queued for the app, which is woken up to act on them. Linux reports no clicks on the icon
+//! itself, so the menu offers "Show window" as well.
+
+use std::sync::{Arc, Mutex};
+
+use eframe::egui;
+use tray_icon::menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem};
+use tray_icon::{Icon, MouseButton, MouseButtonState, TrayIcon, TrayIconBuilder, TrayIconEvent};
+
+const SHOW: &str = "show";
+const START: &str = "start";
+const STOP: &str = "stop";
+const QUIT: &str = "quit";
+
+/// What was asked for in the tray.
+#[derive(Clone, Copy, PartialEq)]
+pub enum TrayAction {
+    Show,
+    /// Fetch lyrics for the selected folder into the checked outputs.
+    Start,
+    /// Cancel the current run.
+    Stop,
+    Quit,
+}
+
+pub struct Tray {
+    actions: Arc<Mutex<Vec<TrayAction>>>,
+    _icon: IconHandle,
+}
+
+/// Keeps the icon in the tray until dropped. On Linux its thread owns it instead.
+#[cfg(target_os = "linux")]
+type IconHandle = ();
+#[cfg(not(target_os = "linux"))]
+type IconHandle = TrayIcon;
+
+impl Tray {
+    /// Puts the icon in the tray, repainting `ctx` whenever it is clicked or its menu used.
+    pub fn new(ctx: &egui::Context) -> Result<Self, String> {
+        let actions = Arc::new(Mutex::new(Vec::new()));
+        let queue = |actions: &Arc<Mutex<Vec<TrayAction>>>| {
+            let (actions, ctx) = (actions.clone(), ctx.clone());
+            move |action: TrayAction| {
+                actions.lock().unwrap().push(action);
+                ctx.request_repaint();
+            }
+        };
+        let picked = queue(&actions);
+        MenuEvent::set_event_handler(Some(move |event: MenuEvent| match event.id.as_ref() {
+            SHOW => picked(TrayAction::Show),
+            START => picked(TrayAction::Start),
+            STOP => picked(TrayAction::Stop),
+            QUIT => picked(TrayAction::Quit),
+            _ => {}
+        }));
+        let clicked = queue(&actions);
+        TrayIconEvent::set_event_handler(Some(move |event: TrayIconEvent| {
+            if let TrayIconEvent::Click { button: MouseButton::Left, button_state: MouseButtonState::Up, .. } = event {
+                clicked(TrayAction::Show);
+            }
+        }));
+
+        Ok(Self { actions, _icon: start_icon()? })
+    }
+
+    /// What was asked for since the last call, oldest first.
+    pub fn take_actions(&self) -> Vec<TrayAction> {
+        std::mem::take(&mut *self.actions.lock().unwrap())
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn start_icon() -> Result<IconHandle, String> {
+    let (started, result) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let icon = gtk::init().map_err(|e| e.to_string()).and_then(|()| build_icon());
+        let _ = started.send(icon.as_ref().map(|_| ()).map_err(String::clone));
+        if icon.is_ok() {
+            gtk::main();
+        }
+    });
+    result.recv().map_err(|_| "the tray icon's thread stopped".to_string())?
+}
+
+#[cfg(not(target_os = "linux"))]
+fn start_icon() -> Result<IconHandle, String> {
+    build_icon()
+}
+
+/// The app's icon with its menu, which has to be built on the thread running the tray's event loop.
+fn build_icon() -> Result<TrayIcon, String> {
+    let image = image::load_from_memory(include_bytes!("../icon.png")).map_err(|e| e.to_string())?.into_rgba8();
+    let (width, height) = image.dimensions();
+    let icon = Icon::from_rgba(image.into_raw(), width, height).map_err(|e| e.to_string())?;
+    let menu = Menu::new();
+    menu.append_items(&[
+        &MenuItem::with_id(SHOW, "Show window", true, None),
+        &MenuItem::with_id(START, "Fetch lyrics", true, None),
+        &MenuItem::with_id(STOP, "Cancel", true, None),
+        &PredefinedMenuItem::separator(),
+        &MenuItem::with_id(QUIT, "Quit", true, None),
+    ])
+    .map_err(|e| e.to_string())?;
+    TrayIconBuilder::new()
+        .with_icon(icon)
+        .with_menu(Box::new(menu))
+        .with_tooltip("Lyrics Downloader")
+        .build()
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,232 @@
+//! Undoing the last run.
+//!
+//! Every run that writes files keeps a JSON-lines log of what it changed, with what was there
+//! before: the files it created, the previous contents of the `.lrc` files it replaced, and the
+//! lyrics the audio files it tagged or converted held in their tags. A new run replaces the log,
+//! so only the last run can be undone. A resumed run adds to the log of the run it resumes.
+//! Undoing deletes the created files and puts the previous contents and lyrics back, newest
+//! change first.
+//!
+//! For tags, the lyrics fields of every tag of the file are kept, including the provenance fields
+//! and the stale copies that embedding removes, and so are the USLT and SYLT frames of an ID3v2
+//! tag. Other fields aren't touched. An ID3v2 tag removed from a FLAC file isn't put back.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use lofty::{AudioFile, ItemKey, Tag, TagType, TaggedFileExt};
+use serde::{Deserialize, Serialize};
+
+use crate::sylt::{self, SavedFrame};
+use crate::{lyrics_tag_type, provenance, read_track, Track, STALE_LYRICS_KEYS};
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+enum UndoEntry {
+    Started { started_at: String, folders: Vec<PathBuf> },
+    /// `file` didn't exist before the run.
+    Created { file: PathBuf },
+    /// `file` held `previous` before the run.
+    Replaced { file: PathBuf, previous: String },
+    /// The audio file `file` held `lyrics` in its tags before the run.
+    Tagged { file: PathBuf, lyrics: TagLyrics },
+}
+
+/// The lyrics an audio file holds in its tags, to put back on undo.
+#[derive(Serialize, Deserialize)]
+pub struct TagLyrics {
+    fields: Vec<LyricsField>,
+    /// USLT and SYLT frames of the ID3v2 tag, or None if the file has no ID3v2 tag type or its
+    /// frames couldn't be read.
+    frames: Option<Vec<SavedFrame>>,
+}
+
+/// A field of a tag that `is_lyrics_field`.
+#[derive(Serialize, Deserialize)]
+struct LyricsField {
+    tag: String,
+    /// Name of the field, or None for the tag's lyrics field.
+    key: Option<String>,
+    value: String,
+}
+
+/// Tag types by the names they are logged under.
+const TAG_TYPES: [(TagType, &str); 7] = [
+    (TagType::Ape, "ape"),
+    (TagType::Id3v1, "id3v1"),
+    (TagType::Id3v2, "id3v2"),
+    (TagType::Mp4Ilst, "mp4_ilst"),
+    (TagType::VorbisComments, "vorbis_comments"),
+    (TagType::RiffInfo, "riff_info"),
+    (TagType::AiffText, "aiff_text"),
+];
+
+impl TagLyrics {
+    /// The lyrics of `track` as read by `read_track`, or None if its file couldn't be read.
+    pub fn of(track: &Track) -> Option<Self> {
+        let tagged_file = track.tagged_file.as_ref().ok()?;
+        let mut fields = Vec::new();
+        for tag in tagged_file.tags() {
+            let Some((_, name)) = TAG_TYPES.iter().find(|(tag_type, _)| *tag_type == tag.tag_type()) else {
+                continue;
+            };
+            for item in tag.items().filter(|item| is_lyrics_field(item.key())) {
+                let Some(value) = item.value().text() else {
+                    continue;
+                };
+                let key = match item.key() {
+                    ItemKey::Unknown(key) => Some(key.clone()),
+                    _ => None,
+                };
+                fields.push(LyricsField { tag: name.to_string(), key, value: value.to_string() });
+            }
+        }
+        let frames = (lyrics_tag_type(track) == TagType::Id3v2).then(|| sylt::lyrics_frames(&track.path).ok()).flatten();
+        Some(Self { fields, frames })
+    }
+}
+
+/// Whether `key` holds lyrics or their provenance.
+fn is_lyrics_field(key: &ItemKey) -> bool {
+    match key {
+        ItemKey::Lyrics => true,
+        ItemKey::Unknown(key) => {
+            STALE_LYRICS_KEYS.iter().chain(&provenance::KEYS).any(|lyrics_key| key.eq_ignore_ascii_case(lyrics_key))
+        }
+        _ => false,
+    }
+}
+
+/// The log of the run in progress.
+pub struct UndoLog {
+    file: Mutex<File>,
+}
+
+impl UndoLog {
+    /// Starts a new log at `path` for a run over `folders`, replacing the previous run's.
+    pub fn start(path: &Path, folders: &[PathBuf]) -> io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let log = Self { file: Mutex::new(File::create(path)?) };
+        let started_at = chrono::Local::now().format("%Y-%m-%dT%H:%M:%S").to_string();
+        log.append(&UndoEntry::Started { started_at, folders: folders.to_vec() })?;
+        Ok(log)
+    }
+
+    /// Continues the log at `path` of an interrupted run that is resumed, keeping its changes.
+    pub fn resume(path: &Path) -> io::Result<Self> {
+        Ok(Self { file: Mutex::new(OpenOptions::new().append(true).open(path)?) })
+    }
+
+    /// Records that `file` was written, holding `previous` before or not existing if None.
+    pub fn written(&self, file: &Path, previous: Option<&str>) {
+        let file = file.to_path_buf();
+        let _ = self.append(&match previous {
+            Some(previous) => UndoEntry::Replaced { file, previous: previous.to_string() },
+            None => UndoEntry::Created { file },
+        });
+    }
+
+    /// Records that the tags of `file` were changed, which held `lyrics` before.
+    pub fn tagged(&self, file: &Path, lyrics: TagLyrics) {
+        let _ = self.append(&UndoEntry::Tagged { file: file.to_path_buf(), lyrics });
+    }
+
+    fn append(&self, entry: &UndoEntry) -> io::Result<()> {
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+        // Flushed right away, so a crash mid-run leaves a log that undoes what was written
+        let mut file = self.file.lock().unwrap();
+        file.write_all(line.as_bytes())?;
+        file.flush()
+    }
+}
+
+/// What the last run changed, as read back from its log.
+pub struct LastRun {
+    pub started_at: String,
+    pub folders: Vec<PathBuf>,
+    entries: Vec<UndoEntry>,
+}
+
+impl LastRun {
+    /// Reads the log at `path`. Returns None if there is none or it records no changes.
+    pub fn load(path: &Path) -> Option<Self> {
+        let mut lines = BufReader::new(File::open(path).ok()?).lines().map_while(Result::ok);
+        let Ok(UndoEntry::Started { started_at, folders }) = serde_json::from_str(&lines.next()?) else {
+            return None;
+        };
+        let entries: Vec<UndoEntry> = lines.filter_map(|line| serde_json::from_str(&line).ok()).collect();
+        (!entries.is_empty()).then_some(Self { started_at, folders, entries })
+    }
+
+    /// Number of changes that can be undone.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Undoes the changes, newest first, logging each one that fails. Returns the number of
+    /// changes undone.
+    ///
+    /// Undoing again is harmless, so the log is only removed from `path` if every change was
+    /// undone, and the others can be retried.
+    pub fn undo(self, path: &Path, logs: &Mutex<Vec<String>>) -> usize {
+        let mut undone = 0;
+        for entry in self.entries.iter().rev() {
+            let (file, result) = match entry {
+                UndoEntry::Started { .. } => continue,
+                UndoEntry::Created { file } => (file, remove(file)),
+                UndoEntry::Replaced { file, previous } => (file, fs::write(file, previous).map_err(|e| e.to_string())),
+                UndoEntry::Tagged { file, lyrics } => (file, restore_tags(file, lyrics)),
+            };
+            match result {
+                Ok(()) => undone += 1,
+                Err(e) => logs.lock().unwrap().push(format!("❌ Failed to undo the changes to {}: {}", file.display(), e)),
+            }
+        }
+        if undone == self.len() {
+            let _ = fs::remove_file(path);
+        }
+        undone
+    }
+}
+
+/// Deletes a file the run created. A file that is already gone counts as deleted.
+fn remove(file: &Path) -> Result<(), String> {
+    match fs::remove_file(file) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.to_string()),
+        _ => Ok(()),
+    }
+}
+
+/// Puts the lyrics fields and frames of `lyrics` back into the tags of `file`, removing the ones
+/// the run added.
+fn restore_tags(file: &Path, lyrics: &TagLyrics) -> Result<(), String> {
+    let mut track = read_track(file.to_path_buf());
+    let tagged_file = track.tagged_file.as_mut().map_err(|e| e.to_string())?;
+    for (tag_type, name) in TAG_TYPES {
+        let fields: Vec<&LyricsField> = lyrics.fields.iter().filter(|field| field.tag == name).collect();
+        if tagged_file.tag(tag_type).is_none() {
+            if fields.is_empty() || !tagged_file.supports_tag_type(tag_type) {
+                continue;
+            }
+            tagged_file.insert_tag(Tag::new(tag_type));
+        }
+        let Some(tag) = tagged_file.tag_mut(tag_type) else {
+            continue;
+        };
+        tag.retain(|item| !is_lyrics_field(item.key()));
+        for field in fields {
+            let key = field.key.clone().map_or(ItemKey::Lyrics, ItemKey::Unknown);
+            tag.insert_text(key, field.value.clone());
+        }
+    }
+    tagged_file.save_to_path(file).map_err(|e| e.to_string())?;
+    match &lyrics.frames {
+        Some(frames) => sylt::restore_frames(file, frames).map_err(|e| e.to_string()),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,75 @@
+//! Watching the library folder for new audio files, such as fresh downloads or rips, so their
+//! lyrics are looked up as they arrive.
+//!
+//! New files are usually still being copied or ripped when they first show up. A file is only
+//! handed out once it has seen no changes for `SETTLE_TIME`.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use notify::event::{ModifyKind, RenameMode};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::audio_extension;
+
+/// How long a new file must stay unchanged before it is processed.
+pub const SETTLE_TIME: Duration = Duration::from_secs(3);
+
+pub struct FolderWatcher {
+    pub folder: PathBuf,
+    /// Stops watching when dropped.
+    _watcher: RecommendedWatcher,
+    /// New audio files that are still settling, with the time of their last change.
+    pending: Arc<Mutex<HashMap<PathBuf, Instant>>>,
+}
+
+impl FolderWatcher {
+    /// Starts watching `folder` and its subfolders, calling `changed` on every change, after which
+    /// new files may settle within `SETTLE_TIME`.
+    pub fn start(folder: &Path, changed: impl Fn() + Send + 'static) -> notify::Result<Self> {
+        let pending = Arc::new(Mutex::new(HashMap::new()));
+        let events = Arc::clone(&pending);
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+            let Ok(event) = event else {
+                return;
+            };
+            let mut pending = events.lock().unwrap();
+            for path in event.paths.into_iter().filter(|path| audio_extension(path).is_some()) {
+                match event.kind {
+                    EventKind::Create(_) | EventKind::Modify(ModifyKind::Name(RenameMode::To | RenameMode::Any)) => {
+                        pending.insert(path, Instant::now());
+                    }
+                    // Writes to a file being copied in. Changes to files that were already there,
+                    // including the lyrics embedded by this app, don't count as new files.
+                    EventKind::Modify(_) => {
+                        if let Some(changed) = pending.get_mut(&path) {
+                            *changed = Instant::now();
+                        }
+                    }
+                    EventKind::Remove(_) => {
+                        pending.remove(&path);
+                    }
+                    _ => {}
+                }
+            }
+            changed();
+        })?;
+        watcher.watch(folder, RecursiveMode::Recursive)?;
+        Ok(Self { folder: folder.to_path_buf(), _watcher: watcher, pending })
+    }
+
+    /// Removes and returns the new files that haven't changed for `SETTLE_TIME`, sorted by path.
+    pub fn take_settled(&self) -> Vec<PathBuf> {
+        let mut pending = self.pending.lock().unwrap();
+        let mut settled: Vec<PathBuf> =
+            pending.iter().filter(|(_, changed)| changed.elapsed() >= SETTLE_TIME).map(|(path, _)| path.clone()).collect();
+        for path in &settled {
+            pending.remove(path);
+        }
+        settled.retain(|path| path.is_file());
+        settled.sort();
+        settled
+    }
+}